@@ -0,0 +1,109 @@
+use super::*;
+use super::roll::modifiers;
+
+use rand::distributions::{Distribution, Uniform};
+
+pub struct Check
+{
+	rng: Source,
+}
+
+impl Check
+{
+	pub fn new_action() -> Box<dyn Action>
+	{
+		Check::with_source(Default::default())
+	}
+
+	/// Creates the action rolling its dice with the given, possibly shared, source.
+	pub fn with_source(rng: Source) -> Box<dyn Action>
+	{
+		Box::new(Check
+		{
+			rng,
+		})
+	}
+}
+
+/// Rolls a single die against a quality (Eigenschaftsprobe) of the *Hero*, supports modifiers.
+///
+/// A 1 is always a success, a 20 is always a failure.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Quality::*;
+/// # use dsa::Hero;
+/// # use dsa::output::Output;
+/// # use dsa::commands::Check;
+/// let mut check = Check::new_action();
+/// # let mut hero = Hero::default();
+/// hero.qualities.insert(Strength,14);
+/// let matches = check.usage().get_matches_from(&["check","-m","3","KK"]);
+/// let output = check.call(&hero,&matches).unwrap();
+/// assert_eq!(1,output.len());
+///
+/// if let Output::Check { check, value, mods, die, success } = &output[0] {
+///     assert_eq!(Strength, *check);
+///     assert_eq!(14, *value);
+///     assert_eq!(3, *mods);
+///     assert_eq!(*die <= 11 && *die != 20 || *die == 1, *success);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Check
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("check")
+			.about("roll for a quality")
+			.arg
+				( Arg::new("modifier")
+				.short('m')
+				.long("modifier")
+				.alias("mod")
+				.help("modification as positive (bad) or negative (good) integer")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.number_of_values(1)
+				)
+			.arg
+				( Arg::new("quality")
+				.value_name("QUALITY")
+				.help("the qualities to test, e.g. KK or Mut")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.required(true)
+				)
+	}
+
+	fn call(&mut self, hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let d20: Uniform<_> = (1..21).into();
+		let mut rng = self.rng.clone();
+
+		let mods = modifiers(matches, "modifier")?;
+
+		matches.values_of("quality")
+			.unwrap()
+			.map(|quality|
+			{
+				let check = quality.parse::<Quality>()?;
+				let value = *hero.qualities.get(&check).ok_or_else(|| ErrorKind::MissingQuality(format!("{:?}", check)))?;
+				let die = d20.sample(&mut rng);
+
+				Ok(Output::Check
+				{
+					success: die == 1 || (die != 20 && die <= value - mods),
+					check,
+					value,
+					mods,
+					die,
+				})
+			})
+			.collect()
+	}
+}
@@ -1,99 +1,615 @@
 use super::*;
 use crate::app;
+use crate::config;
 use crate::output;
 use crate::output::Output;
+use crate::session::{Journal, Session};
 
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, IsTerminal};
+use std::path::PathBuf;
+use std::rc::Rc;
 
-use rustyline::{Config, Editor};
+use clap::ValueSource;
+use error_chain::bail;
+use rustyline::{CompletionType, Config, Editor};
+use rustyline::error::ReadlineError;
 
-pub struct Cli;
+mod alias;
+mod completion;
+use alias::Alias;
+use completion::Completion;
 
-// TODO: more docs and examples
-/// Boots up a cli interface with trackers holding mutable state.
-impl Action for Cli
+pub struct Cli
 {
-	fn usage<'a,'b>(&'a self) -> Command<'b>
+	rng: Source,
+	party: Roster,
+	failures: Rc<RefCell<Vec<Failure>>>,
+}
+
+impl Cli
+{
+	pub fn new_action() -> Box<dyn Action>
 	{
-		Command::new("cli")
-			.about("interactive command line client")
+		Cli::with_source(Default::default())
 	}
 
-	fn call(&mut self,hero: &Hero,_: &ArgMatches) -> Result<Vec<Output>>
+	/// Creates the cli sharing the given source with all of its dice rolling actions.
+	pub fn with_source(rng: Source) -> Box<dyn Action>
 	{
-		let subcommands = vec!
-			[ Dump::new_action()
-			, Roll::new_action()
-			, Tracker::new_action("health",hero.health,hero.health)
-			, Tracker::new_action("astral",hero.astral,hero.astral)
-			, Tracker::new_action("stamina",hero.stamina,hero.stamina)
-			];
-		let mut subcommands: HashMap<String,Box<dyn Action>> = subcommands.into_iter()
-			.map(|command|
+		Cli::with_party(rng,Default::default())
+	}
+
+	/// Creates the cli for a whole party, see *Roster*.
+	pub fn with_party(rng: Source, party: Roster) -> Box<dyn Action>
+	{
+		Cli::with_failures(rng,party,Default::default())
+	}
+
+	/// Creates the cli for a party along with the hero files which failed to load, see *Validate::with_failures*.
+	pub fn with_failures(rng: Source, party: Roster, failures: Rc<RefCell<Vec<Failure>>>) -> Box<dyn Action>
+	{
+		Box::new(Cli
+		{
+			rng,
+			party,
+			failures,
+		})
+	}
+}
+
+/// How many changes can be undone in a cli session.
+const UNDO_LIMIT: usize = 256;
+
+/// Reverts or reapplies the last change of any tracker.
+struct Undo
+{
+	redo: bool,
+	session: Rc<RefCell<Session>>,
+	journal: Rc<RefCell<Journal>>,
+}
+
+impl Action for Undo
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		if self.redo
+		{
+			Command::new("redo")
+				.about("reapply the last undone tracker change")
+		}
+		else
+		{
+			Command::new("undo")
+				.about("revert the last tracker change")
+		}
+	}
+
+	fn call(&mut self, _: &Hero, _: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let mut session = self.session.borrow_mut();
+		let before = session.gauges.clone();
+		let mut journal = self.journal.borrow_mut();
+		let done = if self.redo { journal.redo(&mut session) } else { journal.undo(&mut session) };
+		if !done
+		{
+			bail!("nothing to {}", if self.redo { "redo" } else { "undo" });
+		}
+
+		// show every gauge which was affected
+		Ok(session.gauges.iter()
+			.filter(|(name,gauge)| before.get(*name) != Some(gauge))
+			.map(|(name,gauge)| Output::Gauge
 			{
-				(command.usage().get_name().to_owned(),command)
+				name: name.to_string(),
+				current: gauge.current,
+				max: gauge.max,
 			})
-			.collect();
+			.collect())
+	}
+}
+
+/// Switches the active hero of the party.
+struct Use
+{
+	/// The names of all heroes in the party.
+	names: Vec<String>,
+	active: Rc<Cell<usize>>,
+}
+
+impl Action for Use
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("use")
+			.about("switch to another hero of the party")
+			.arg
+				( Arg::new("hero")
+				.value_name("NAME")
+				.help("the name of the hero, any unique prefix will do")
+				.takes_value(true)
+				.required(true)
+				)
+	}
+
+	fn call(&mut self, _: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		self.active.set(Hero::select(&self.names, matches.value_of("hero").unwrap())?);
+		Ok(vec![])
+	}
+}
+
+/// A hero of the party along with its own trackers.
+///
+/// The session and journal of the active hero are kept in the *Shell* instead, where the actions access them.
+struct Member<'a>
+{
+	hero: &'a Hero,
+	/// The session file, written after every change.
+	path: Option<PathBuf>,
+	session: Session,
+	journal: Journal,
+}
+
+/// The subcommands of a running cli along with the state they share.
+struct Shell<'a>
+{
+	party: Vec<Member<'a>>,
+	/// The member whose session and journal are currently in use.
+	current: usize,
+	/// The member selected by `use`.
+	active: Rc<Cell<usize>>,
+	rng: Source,
+	/// The format used unless a command line asks for another one.
+	format: output::Format,
+	session: Rc<RefCell<Session>>,
+	journal: Rc<RefCell<Journal>>,
+	config: Rc<RefCell<config::Config>>,
+	subcommands: HashMap<String,Box<dyn Action>>,
+	/// The names of all subcommands which are trackers of a gauge.
+	trackers: HashSet<String>,
+}
+
+impl<'a> Shell<'a>
+{
+	fn hero(&self) -> &'a Hero
+	{
+		self.party[self.current].hero
+	}
+
+	/// Swaps the session and journal of the hero selected by `use` in.
+	fn switch(&mut self)
+	{
+		let active = self.active.get();
+		if active == self.current
+		{
+			return;
+		}
+		for index in [self.current,active]
+		{
+			let member = &mut self.party[index];
+			std::mem::swap(&mut member.session, &mut self.session.borrow_mut());
+			std::mem::swap(&mut member.journal, &mut self.journal.borrow_mut());
+		}
+		self.current = active;
+	}
+
+	/// The prompt, naming the active hero if there is more than one.
+	fn prompt(&self) -> String
+	{
+		match self.party.len()
+		{
+			1 => "% ".to_string(),
+			_ => format!("{} % ", self.hero().name),
+		}
+	}
+
+	/// Keeps one tracker per gauge, as trackers can be created and removed at runtime.
+	fn sync_trackers(&mut self)
+	{
+		let gauges: Vec<String> = self.session.borrow().gauges.keys().cloned().collect();
+		let subcommands = &mut self.subcommands;
+		self.trackers.retain(|name|
+		{
+			let keep = gauges.contains(name);
+			if !keep
+			{
+				subcommands.remove(name);
+			}
+			keep
+		});
+		for name in gauges
+		{
+			if !self.subcommands.contains_key(&name)
+			{
+				self.subcommands.insert(name.clone(),Tracker::with_session(name.clone(),self.session.clone()));
+				self.trackers.insert(name);
+			}
+		}
+	}
+
+	/// Formats an error using the format of the cli.
+	fn format_error(&self, error: &Error) -> String
+	{
+		Box::<dyn output::Formatter>::from(self.format).format_error(error)
+	}
+
+	/// Splits a line into its commands, with all aliases expanded.
+	fn expand(&self, line: &str) -> Result<Vec<Vec<String>>>
+	{
+		alias::expand(&self.config.borrow().aliases, line)
+	}
+
+	/// Runs a single command, returning its formatted output.
+	///
+	/// Changes to the session are recorded for undo and persisted right away so nothing is lost when the terminal is closed.
+	fn execute(&mut self, words: Vec<String>) -> Result<Vec<String>>
+	{
+		self.sync_trackers();
+
+		let before = self.session.borrow().clone();
+		// build the clap Command
+		let app = app().subcommands(self.subcommands.values().map(|command| command.usage()));
+		// hackily insert an empty string as argv[0]
+		let result: Result<Vec<_>> = app.try_get_matches_from(std::iter::once(String::new()).chain(words))
+			.map_err(Error::from)
+			.and_then(|matches|
+			{
+				// get the corresponding subcommand
+				let (name, args) = matches.subcommand().unwrap();
+				// we only add subcommands from that hashmap so it MUST be present
+				let command = self.subcommands.get_mut(name).unwrap_or_else(|| unreachable!());
+
+				if let Some(seed) = matches.value_of("seed")
+				{
+					self.rng.reseed(seed.parse()?);
+				}
+
+				let formatter: Box<dyn output::Formatter> = match matches.value_source("format")
+				{
+					Some(ValueSource::DefaultValue) => self.format,
+					_ => matches.value_of("format").map(|format| output::Format::from_str(format, true)).unwrap().unwrap(),
+				}.into();
 
-		let mut rl = Editor::<()>::with_config(Config::builder()
+				let result = command.call(self.party[self.current].hero, args)?.into_iter()
+					.map(|result| formatter.format(&result))
+					.collect();
+
+				if name != "undo" && name != "redo" && *self.session.borrow() != before
+				{
+					self.journal.borrow_mut().record(&before);
+				}
+
+				Ok(result)
+			});
+
+		if let Some(ref path) = self.party[self.current].path
+		{
+			let session = self.session.borrow();
+			if *session != before
+			{
+				if let Err(error) = session.save(path)
+				{
+					eprintln!("{}", self.format_error(&error));
+				}
+			}
+		}
+
+		self.switch();
+
+		result
+	}
+
+	/// Tab completion for all current subcommands and aliases.
+	fn completion(&self) -> Completion
+	{
+		let hero = self.hero();
+		let mut values: HashMap<String,Vec<String>> = HashMap::new();
+		let skills: Vec<String> = hero.skills.keys().cloned().collect();
+		values.insert("roll".to_string(),skills.clone());
+		values.insert("odds".to_string(),skills.clone());
+		values.insert("party".to_string(),skills);
+		let talents: Vec<String> = hero.combat.keys().cloned().collect();
+		values.insert("attack".to_string(),talents.clone());
+		values.insert("parry".to_string(),talents);
+		values.insert("damage".to_string(),hero.weapons.iter().chain(&hero.ranged_weapons).map(|weapon| weapon.name.to_lowercase()).collect());
+		values.insert("check".to_string(),vec!["MU","KL","IN","CH","FF","GE","KO","KK"].into_iter().map(str::to_string).collect());
+		values.insert("log".to_string(),self.session.borrow().gauges.keys().cloned().collect());
+		let aliases: Vec<String> = self.config.borrow().aliases.keys().cloned().collect();
+		values.insert("alias".to_string(),aliases.clone());
+		values.insert("use".to_string(),self.party.iter().map(|member| member.hero.name.clone()).collect());
+
+		Completion::new(app(),self.subcommands.values().map(|command| command.usage())
+			.chain(aliases.into_iter().map(Command::new)),values)
+	}
+
+	/// Reads commands from the terminal until `exit` or the end of input.
+	fn interactive(&mut self, history: Option<PathBuf>)
+	{
+		let mut rl = Editor::<Completion>::with_config(Config::builder()
 			.max_history_size(1024*512) // with 80 characters per line that's 40MiB
 			.history_ignore_dups(false)
 			.history_ignore_space(true)
 			.auto_add_history(true)
+			.completion_type(CompletionType::List)
 			.tab_stop(4)
 			.build());
-		for line in rl.iter("% ")
+		if let Some(ref history) = history
 		{
-			let words = line
-				.map_err(|err| err.into())
-				.and_then(|line| Ok(shell_words::split(&line)?));
-
-			if let Ok(ref args) = words
+			// there is no history yet on the first run
+			if history.exists()
 			{
-				if args.eq(&["exit"])
+				if let Err(error) = rl.load_history(history)
 				{
-					break;
+					let error = Error::from(error).chain_err(|| format!("failed loading history '{}'", history.display()));
+					eprintln!("{}", self.format_error(&error));
 				}
 			}
+		}
 
-			let result: Result<Vec<_>> = words
-				// build the clap Command
-				.and_then(|words|
+		'repl: loop
+		{
+			self.sync_trackers();
+			rl.set_helper(Some(self.completion()));
+
+			let line = match rl.readline(&self.prompt())
+			{
+				Err(ReadlineError::Eof) => break,
+				line => line,
+			};
+			let commands = match line
+				.map_err(Error::from)
+				.and_then(|line| self.expand(&line))
+			{
+				Ok(commands) => commands,
+				Err(error) =>
 				{
-					let app = app().subcommands(subcommands.values().map(|command| command.usage()));
-					// hackily insert an empty string as argv[0]
-					Ok(app.try_get_matches_from(std::iter::once(String::new()).chain(words))?)
-				})
-				.and_then(|matches|
+					eprintln!("{}", self.format_error(&error));
+					continue;
+				},
+			};
+
+			for words in commands
+			{
+				if words.eq(&["exit"])
 				{
-					// get the corresponding subcommand
-					let (command, args) = matches.subcommand().unwrap();
-					// we only add subcommands from that hashmap so it MUST be present
-					let command = subcommands.get_mut(command).unwrap_or_else(|| unreachable!());
+					break 'repl;
+				}
 
-					let formatter: Box<dyn output::Formatter> = matches.value_of("format").map(|format| output::Format::from_str(format, true)).unwrap().unwrap().into();
+				match self.execute(words)
+				{
+					Ok(outputs) =>
+					{
+						for output in outputs
+						{
+							println!("{}",output)
+						}
+					},
+					Err(error) => eprintln!("{}", self.format_error(&error)),
+				}
+			}
+		}
 
-					let result = command.call(hero, args)?.into_iter()
-						.map(|result| formatter.format(&result))
-						.collect();
+		if let Some(ref history) = history
+		{
+			let saved = match history.parent()
+			{
+				Some(dir) => std::fs::create_dir_all(dir).map_err(ReadlineError::from),
+				None => Ok(()),
+			}.and_then(|_| rl.save_history(history));
+			if let Err(error) = saved
+			{
+				let error = Error::from(error).chain_err(|| format!("failed saving history '{}'", history.display()));
+				eprintln!("{}", self.format_error(&error));
+			}
+		}
+	}
 
-					Ok(result)
-				});
+	/// Runs the commands of a script line by line, stopping at the first error unless told to keep going.
+	///
+	/// When keeping going, the errors are reported right away and the script fails at the end instead.
+	fn script<R: BufRead>(&mut self, input: R, keep_going: bool) -> Result<()>
+	{
+		let mut failed = 0;
+		for (number,line) in input.lines().enumerate()
+		{
+			let result = line
+				.map_err(Error::from)
+				.and_then(|line| self.expand(&line))
+				.and_then(|commands|
+				{
+					for words in commands
+					{
+						if words.eq(&["exit"])
+						{
+							return Ok(false);
+						}
+						for output in self.execute(words)?
+						{
+							println!("{}",output)
+						}
+					}
+					Ok(true)
+				})
+				.chain_err(|| format!("line {} of the script", number + 1));
 
 			match result
 			{
-				Ok(outputs) =>
+				Ok(true) => {},
+				Ok(false) => break,
+				Err(error) if keep_going =>
 				{
-					for output in outputs
-					{
-						println!("{}",output)
-					}
+					eprintln!("{}", self.format_error(&error));
+					failed += 1;
 				},
-				Err(error) => eprintln!("{}", error.description()),
+				Err(error) => return Err(error),
 			}
 		}
 
-		Ok(vec![])
+		if failed > 0
+		{
+			bail!("{} line{} of the script failed", failed, if failed == 1 { "" } else { "s" });
+		}
+		Ok(())
 	}
 }
 
+/// Boots up a cli interface with trackers holding mutable state.
+///
+/// The state of the trackers is restored from a per-hero session file and written back after every change.
+/// With a whole party loaded, `use` switches between the heroes, each keeping its own trackers and undo history.
+///
+/// Instead of prompting, the commands can also be read from a script, line by line.
+/// This happens with `--script` or whenever the input is not a terminal.
+/// Lines starting with `#` are comments.
+impl Action for Cli
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("cli")
+			.about("interactive command line client")
+			.arg
+				( Arg::new("fresh")
+				.long("fresh")
+				.help("start with all trackers at their max values instead of restoring the session")
+				)
+			.arg
+				( Arg::new("no-history")
+				.long("no-history")
+				.help("neither load nor save the input history")
+				)
+			.arg
+				( Arg::new("script")
+				.long("script")
+				.value_name("FILE")
+				.help("run the commands of a file instead of prompting, '-' reads from stdin")
+				.takes_value(true)
+				)
+			.arg
+				( Arg::new("keep-going")
+				.long("keep-going")
+				.short('k')
+				.help("do not stop the script at the first failing command")
+				)
+	}
+
+	fn call(&mut self,hero: &Hero,matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let heroes = self.party.heroes(hero);
+		// the session file and the format are global options of the main app, shared with the non-interactive commands
+		let session = matches.try_get_one::<String>("session").ok().flatten().map(PathBuf::from);
+		if session.is_some() && heroes.len() > 1
+		{
+			bail!("a single session file cannot be shared by a party, leave out --session");
+		}
+		let format = matches.try_get_one::<String>("format").ok().flatten()
+			.map(|format| output::Format::from_str(format, true))
+			.unwrap_or(Ok(output::Format::HumanReadable))?;
+		for hero in heroes.iter().filter(|hero| !hero.warnings.is_empty())
+		{
+			eprintln!("{}: {} problem{} found in the hero file, see 'validate'", hero.name, hero.warnings.len(), if hero.warnings.len() == 1 { "" } else { "s" });
+		}
+		let config = config::Config::load_default()?;
+		let history = config.history.clone().filter(|_| !matches.is_present("no-history"));
+
+		let mut party = heroes.iter()
+			.map(|hero|
+			{
+				let path = session.clone().or_else(|| Session::default_path(hero));
+				let mut session = match path
+				{
+					Some(ref path) if path.exists() && !matches.is_present("fresh") => Session::load(hero, path)?,
+					_ => Session::new(hero),
+				};
+				config.apply(&mut session);
+				Ok(Member
+				{
+					hero,
+					path,
+					session,
+					journal: Journal::new(UNDO_LIMIT),
+				})
+			})
+			.collect::<Result<Vec<_>>>()?;
+		// heroes of the same name cannot be told apart by `use` and would overwrite each other's session file
+		for (index,member) in party.iter().enumerate()
+		{
+			if let Some(other) = party[..index].iter().find(|other| other.hero.name.to_lowercase() == member.hero.name.to_lowercase() || (other.path.is_some() && other.path == member.path))
+			{
+				if other.hero.name == member.hero.name
+				{
+					bail!("'{}' is part of the party more than once, load every hero only once", member.hero.name);
+				}
+				bail!("'{}' and '{}' would share a session file, load every hero only once", other.hero.name, member.hero.name);
+			}
+		}
+		// the first hero is active from the start, its state is kept in the shell
+		let session = Rc::new(RefCell::new(std::mem::take(&mut party[0].session)));
+		let journal = Rc::new(RefCell::new(std::mem::replace(&mut party[0].journal, Journal::new(UNDO_LIMIT))));
+		let config = Rc::new(RefCell::new(config));
+		let active = Rc::new(Cell::new(0));
+
+		let subcommands = vec!
+			[ Dump::new_action()
+			, Roll::with_source(self.rng.clone())
+			, Odds::new_action()
+			, Check::with_source(self.rng.clone())
+			, Damage::with_source(self.rng.clone())
+			, Dice::with_source(self.rng.clone())
+			, Combat::with_source(Quality::CloseCombat,self.rng.clone())
+			, Combat::with_source(Quality::Parry,self.rng.clone())
+			, Log::with_session(session.clone())
+			, Party::with_party(self.party.clone(),self.rng.clone())
+			, Validate::with_failures(self.party.clone(),self.failures.clone())
+			, Box::new(Undo { redo: false, session: session.clone(), journal: journal.clone() })
+			, Box::new(Undo { redo: true, session: session.clone(), journal: journal.clone() })
+			];
+		let mut subcommands: HashMap<String,Box<dyn Action>> = subcommands.into_iter()
+			.map(|command|
+			{
+				(command.usage().get_name().to_owned(),command)
+			})
+			.collect();
+		let mut reserved: Vec<String> = subcommands.keys().cloned().collect();
+		reserved.extend(RESERVED.iter().map(|name| name.to_string()));
+		subcommands.insert("track".to_string(),Track::with_config(session.clone(),reserved.clone(),config.clone()));
+		subcommands.insert("alias".to_string(),Alias::with_config(config.clone(),config::Config::default_path(),reserved,session.clone()));
+		if party.len() > 1
+		{
+			let names = party.iter().map(|member| member.hero.name.clone()).collect();
+			subcommands.insert("use".to_string(),Box::new(Use { names, active: active.clone() }));
+		}
+
+		let mut shell = Shell
+		{
+			party,
+			current: 0,
+			active,
+			rng: self.rng.clone(),
+			format,
+			session,
+			journal,
+			config,
+			subcommands,
+			// trackers are registered for every gauge of the session before each command
+			trackers: HashSet::new(),
+		};
+
+		let keep_going = matches.is_present("keep-going");
+		match matches.value_of("script")
+		{
+			Some("-") => shell.script(std::io::stdin().lock(), keep_going)?,
+			Some(script) =>
+			{
+				let file = File::open(script).chain_err(|| format!("loading script '{}'", script))?;
+				shell.script(BufReader::new(file), keep_going)?;
+			},
+			None if !std::io::stdin().is_terminal() => shell.script(std::io::stdin().lock(), keep_going)?,
+			None => shell.interactive(history),
+		}
+
+		Ok(vec![])
+	}
+}
@@ -0,0 +1,296 @@
+use super::*;
+
+use std::collections::BTreeMap;
+
+/// Defines, shows, lists, and removes the aliases of the cli.
+///
+/// An alias may expand to several commands separated by `;`, a macro so to say.
+/// Everything following `alias` belongs to the alias, so a macro can be defined without quoting it.
+/// All changes are written to the configuration file right away.
+pub struct Alias
+{
+	config: Rc<RefCell<config::Config>>,
+	path: Option<PathBuf>,
+	reserved: Vec<String>,
+	session: Rc<RefCell<Session>>,
+}
+
+impl Alias
+{
+	/// Creates the action, aliases must not be named like any of the *reserved* commands or the trackers of the *session*.
+	pub fn with_config(config: Rc<RefCell<config::Config>>, path: Option<PathBuf>, reserved: Vec<String>, session: Rc<RefCell<Session>>) -> Box<dyn Action>
+	{
+		Box::new(Alias
+		{
+			config,
+			path,
+			reserved,
+			session,
+		})
+	}
+
+	fn save(&self) -> Result<()>
+	{
+		match self.path
+		{
+			Some(ref path) => self.config.borrow().save_aliases(path),
+			None => bail!("cannot determine the config file to save aliases in"),
+		}
+	}
+}
+
+impl Action for Alias
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("alias")
+			.about("define, show, or list shortcuts for command lines")
+			.trailing_var_arg(true)
+			.arg
+				( Arg::new("remove")
+				.short('r')
+				.long("remove")
+				.help("remove the alias")
+				.requires("name")
+				.conflicts_with("expansion")
+				)
+			.arg
+				( Arg::new("name")
+				.value_name("NAME")
+				.help("the name of the alias, lists all aliases if omitted")
+				.takes_value(true)
+				)
+			.arg
+				( Arg::new("expansion")
+				.value_name("COMMAND")
+				.help("the command line to run instead, optionally preceded by '=', several commands are separated by ';'")
+				.takes_value(true)
+				.multiple_values(true)
+				.allow_hyphen_values(true)
+				)
+	}
+
+	fn call(&mut self, _: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let name = match matches.value_of("name")
+		{
+			Some(name) => name,
+			None =>
+			{
+				return Ok(self.config.borrow().aliases.iter()
+					.map(|(name,expansion)| Output::Alias
+					{
+						name: name.to_string(),
+						expansion: expansion.to_string(),
+					})
+					.collect());
+			},
+		};
+		// `alias heal=health --add 5` is fine too
+		let (name,first) = match name.split_once('=')
+		{
+			Some((name,first)) => (name,Some(first).filter(|first| !first.is_empty())),
+			None => (name,None),
+		};
+		let mut words: Vec<&str> = first.into_iter().chain(matches.values_of("expansion").into_iter().flatten()).collect();
+		if first.is_none() && words.first() == Some(&"=")
+		{
+			words.remove(0);
+		}
+
+		if matches.is_present("remove")
+		{
+			if self.config.borrow_mut().aliases.remove(name).is_none()
+			{
+				bail!("unknown alias '{}'", name);
+			}
+			self.save()?;
+			return Ok(vec![]);
+		}
+
+		let expansion = match words.len()
+		{
+			0 =>
+			{
+				let config = self.config.borrow();
+				let expansion = config.aliases.get(name).ok_or_else(|| format!("unknown alias '{}'", name))?;
+				return Ok(vec![Output::Alias
+				{
+					name: name.to_string(),
+					expansion: expansion.to_string(),
+				}]);
+			},
+			// a single word is taken as is, so a quoted command line keeps working
+			1 => words[0].to_string(),
+			_ => words.iter()
+				.map(|word| word.split(';')
+					.map(|part| if part.is_empty() { part.to_string() } else { shell_words::quote(part).into_owned() })
+					.collect::<Vec<_>>()
+					.join(";"))
+				.collect::<Vec<_>>()
+				.join(" "),
+		};
+
+		if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == ';')
+		{
+			bail!("invalid alias name '{}'", name);
+		}
+		if self.reserved.iter().any(|reserved| reserved == name) || self.session.borrow().gauges.contains_key(name)
+		{
+			bail!("'{}' is already a command", name);
+		}
+		// an alias which can never run is not worth saving
+		let mut aliases = self.config.borrow().aliases.clone();
+		aliases.insert(name.to_string(),expansion.clone());
+		expand(&aliases, name)?;
+		self.config.borrow_mut().aliases = aliases;
+		self.save()?;
+
+		Ok(vec![Output::Alias
+		{
+			name: name.to_string(),
+			expansion,
+		}])
+	}
+}
+
+/// Splits a line into the words of its commands, expanding the aliases at the start of each command.
+///
+/// Commands are separated by unquoted `;`, except for an `alias` command which takes the rest of the line.
+/// Any words following an alias are appended to the last command of its expansion.
+pub fn expand(aliases: &BTreeMap<String,String>, line: &str) -> Result<Vec<Vec<String>>>
+{
+	expand_with(aliases, line, &mut vec![])
+}
+
+fn expand_with(aliases: &BTreeMap<String,String>, line: &str, active: &mut Vec<String>) -> Result<Vec<Vec<String>>>
+{
+	if line.split_whitespace().next() == Some("alias")
+	{
+		return Ok(vec![shell_words::split(line)?]);
+	}
+
+	let mut commands = vec![];
+	for command in split(line)
+	{
+		let mut words = shell_words::split(&command)?;
+		let alias = words.first().and_then(|first| aliases.get_key_value(first));
+		match alias
+		{
+			Some((name,expansion)) =>
+			{
+				if active.contains(name)
+				{
+					bail!("alias '{}' expands to itself", name);
+				}
+				active.push(name.to_string());
+				let mut expanded = expand_with(aliases, expansion, active)?;
+				active.pop();
+
+				match expanded.last_mut()
+				{
+					Some(last) => last.extend(words.drain(1..)),
+					None => expanded.push(words.drain(1..).collect()),
+				}
+				commands.extend(expanded.into_iter().filter(|words| !words.is_empty()));
+			},
+			None if words.is_empty() => {},
+			None => commands.push(words),
+		}
+	}
+	Ok(commands)
+}
+
+/// Splits a line at every `;` which is neither quoted nor escaped.
+fn split(line: &str) -> Vec<String>
+{
+	let mut commands = vec![String::new()];
+	let mut quote = None;
+	let mut escaped = false;
+	for c in line.chars()
+	{
+		match (quote,c)
+		{
+			_ if escaped => escaped = false,
+			(Some('\''),'\'') | (Some('"'),'"') => quote = None,
+			(Some('\''),_) => {},
+			(_,'\\') => escaped = true,
+			(Some(_),_) => {},
+			(None,'\'') | (None,'"') => quote = Some(c),
+			(None,';') =>
+			{
+				commands.push(String::new());
+				continue;
+			},
+			(None,_) => {},
+		}
+		commands.last_mut().unwrap().push(c);
+	}
+	commands
+}
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn aliases(aliases: &[(&str,&str)]) -> BTreeMap<String,String>
+	{
+		aliases.iter().map(|(name,expansion)| (name.to_string(),expansion.to_string())).collect()
+	}
+
+	fn words(commands: &[&str]) -> Vec<Vec<String>>
+	{
+		commands.iter().map(|command| shell_words::split(command).unwrap()).collect()
+	}
+
+	#[test]
+	fn split_quoted()
+	{
+		assert_eq!(vec!["roll a", " roll b"], split("roll a; roll b"));
+		assert_eq!(vec!["roll 'a;b' \"c;d\"", " e"], split("roll 'a;b' \"c;d\"; e"));
+		assert_eq!(vec![r"roll a\;b", " c"], split(r"roll a\;b; c"));
+		// a backslash does not escape within single quotes
+		assert_eq!(vec![r"roll 'a\'", "b"], split(r"roll 'a\';b"));
+		assert_eq!(vec!["", "roll", ""], split(";roll;"));
+	}
+
+	#[test]
+	fn expand_plain()
+	{
+		let none = aliases(&[]);
+		assert_eq!(words(&["roll a;b"]), expand(&none, "roll 'a;b'").unwrap());
+		assert_eq!(words(&["roll a;b", "health"]), expand(&none, r"roll a\;b; health").unwrap());
+		assert_eq!(words(&["roll x"]), expand(&none, ";; roll x ;").unwrap());
+		assert!(expand(&none, "roll 'x").is_err());
+	}
+
+	#[test]
+	fn expand_aliases()
+	{
+		let aliases = aliases(&
+			[ ("heal","health --add 5")
+			, ("rest","heal; stamina --add 3")
+			, ("look","roll 'sinnenschärfe'")
+			]);
+		assert_eq!(words(&["health --add 5"]), expand(&aliases, "heal").unwrap());
+		assert_eq!(words(&["health --add 5", "stamina --add 3"]), expand(&aliases, "rest").unwrap());
+		assert_eq!(words(&["health --add 5 -r potion"]), expand(&aliases, "heal -r potion").unwrap());
+		assert_eq!(words(&["health --add 5", "stamina --add 3 -r night"]), expand(&aliases, "rest -r night").unwrap());
+		assert_eq!(words(&["roll sinnenschärfe -m 2", "health --add 5"]), expand(&aliases, "look -m 2; heal").unwrap());
+		// only the first word of a command is expanded
+		assert_eq!(words(&["roll heal"]), expand(&aliases, "roll heal").unwrap());
+		// the expansion of an alias being defined is kept as is
+		assert_eq!(words(&["alias x = heal; rest"]), expand(&aliases, "alias x = heal; rest").unwrap());
+	}
+
+	#[test]
+	fn expand_recursion()
+	{
+		assert!(expand(&aliases(&[("x","x")]), "x").is_err());
+		assert!(expand(&aliases(&[("a","b --flag"),("b","health; a")]), "a").is_err());
+		assert!(expand(&aliases(&[("a","b --flag"),("b","health; a")]), "health").is_ok());
+		// using an alias twice is no recursion
+		assert_eq!(words(&["health", "health"]), expand(&aliases(&[("h","health"),("hh","h; h")]), "hh").unwrap());
+	}
+}
@@ -0,0 +1,172 @@
+use clap::Command;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+use std::collections::HashMap;
+
+/// Tab completion for the cli.
+///
+/// Completes the names of the subcommands, their flags, and their positional values (e.g. skill names after `roll`).
+#[derive(Debug,Clone,Default)]
+pub struct Completion
+{
+	/// The flags of the main app, e.g. `--output`.
+	flags: Vec<String>,
+	/// The subcommands, as a map of name to flags and nested subcommands.
+	commands: HashMap<String,(Vec<String>,Vec<String>)>,
+	/// The positional values, as a map of subcommand name to candidates.
+	values: HashMap<String,Vec<String>>,
+}
+
+/// All long and short flags of a command.
+fn flags(command: &Command) -> Vec<String>
+{
+	command.get_arguments()
+		.flat_map(|arg| arg.get_long().map(|long| format!("--{}", long)).into_iter()
+			.chain(arg.get_short().map(|short| format!("-{}", short))))
+		.chain(std::iter::once("--help".to_string()))
+		.collect()
+}
+
+impl Completion
+{
+	/// Collects the completions from the app and all of its subcommands.
+	pub fn new<'a, I: IntoIterator<Item=Command<'a>>>(app: Command<'a>, subcommands: I, values: HashMap<String,Vec<String>>) -> Self
+	{
+		Completion
+		{
+			flags: flags(&app),
+			commands: subcommands.into_iter()
+				.map(|command|
+				{
+					let nested = command.get_subcommands().map(|nested| nested.get_name().to_string()).collect();
+					(command.get_name().to_string(),(flags(&command),nested))
+				})
+				.collect(),
+			values,
+		}
+	}
+
+	/// The start of the word under the cursor and all candidates for it.
+	fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>)
+	{
+		let line = &line[..pos];
+		// whitespace is not necessarily a single byte, e.g. a non-breaking space
+		let start = line.char_indices()
+			.rfind(|(_,c)| c.is_whitespace())
+			.map(|(index,c)| index + c.len_utf8())
+			.unwrap_or(0);
+		let word = &line[start..];
+		let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+		let command = previous.iter().find(|word| self.commands.contains_key(**word));
+		let mut candidates: Vec<String> = match command
+		{
+			None if word.starts_with('-') => self.flags.clone(),
+			None => self.commands.keys().cloned().collect(),
+			Some(_) if word.starts_with('-') => self.commands[*command.unwrap()].0.clone(),
+			Some(command) =>
+			{
+				let (_,nested) = &self.commands[*command];
+				// nested subcommands (e.g. `track new`) come right after the command
+				if !nested.is_empty() && previous.last() == Some(command)
+				{
+					nested.clone()
+				}
+				else
+				{
+					self.values.get(*command).cloned().unwrap_or_default()
+				}
+			},
+		};
+
+		let word = word.to_lowercase();
+		candidates.retain(|candidate| candidate.to_lowercase().starts_with(&word));
+		candidates.sort();
+		candidates.dedup();
+		(start,candidates)
+	}
+}
+
+impl Completer for Completion
+{
+	type Candidate = Pair;
+
+	fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)>
+	{
+		let (start,candidates) = self.candidates(line, pos);
+		Ok((start,candidates.into_iter()
+			.map(|candidate| Pair
+			{
+				replacement: shell_words::quote(&candidate).into_owned(),
+				display: candidate,
+			})
+			.collect()))
+	}
+}
+
+impl Hinter for Completion
+{
+	type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	use clap::Arg;
+
+	fn completion() -> Completion
+	{
+		let app = Command::new("dsa-cli")
+			.arg(Arg::new("format").short('o').long("output"));
+		let subcommands = vec!
+			[ Command::new("roll").arg(Arg::new("modifier").short('m').long("modifier"))
+			, Command::new("track").subcommand(Command::new("new")).subcommand(Command::new("remove"))
+			, Command::new("health")
+			];
+		let values = vec![("roll".to_string(),vec!["sinnenschärfe".to_string(),"schwimmen".to_string()])].into_iter().collect();
+		Completion::new(app, subcommands, values)
+	}
+
+	#[test]
+	fn commands()
+	{
+		let completion = completion();
+		assert_eq!((0,vec!["roll".to_string()]), completion.candidates("ro", 2));
+		assert_eq!((0,vec!["--output".to_string()]), completion.candidates("--o", 3));
+		assert_eq!((6,vec!["new".to_string()]), completion.candidates("track n", 7));
+	}
+
+	#[test]
+	fn values()
+	{
+		let completion = completion();
+		assert_eq!((5,vec!["schwimmen".to_string(),"sinnenschärfe".to_string()]), completion.candidates("roll s", 6));
+		assert_eq!((5,vec!["sinnenschärfe".to_string()]), completion.candidates("roll SINN", 9));
+		assert_eq!((8,vec!["--modifier".to_string()]), completion.candidates("roll -m --m", 11));
+		// only the part before the cursor counts
+		assert_eq!((5,vec!["sinnenschärfe".to_string()]), completion.candidates("roll sinxyz", 8));
+	}
+
+	#[test]
+	fn multibyte_whitespace()
+	{
+		let completion = completion();
+		let line = "roll\u{a0}s";
+		assert_eq!((6,vec!["schwimmen".to_string(),"sinnenschärfe".to_string()]), completion.candidates(line, line.len()));
+		assert_eq!((6,vec!["schwimmen".to_string(),"sinnenschärfe".to_string()]), completion.candidates("roll\u{a0}", 6));
+	}
+}
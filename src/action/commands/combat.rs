@@ -0,0 +1,136 @@
+use super::*;
+use super::roll::modifiers;
+
+use rand::distributions::{Distribution, Uniform};
+
+pub struct Combat
+{
+	check: Quality,
+	rng: Source,
+}
+
+impl Combat
+{
+	/// Creates the attack (*Quality::CloseCombat*) or parry (*Quality::Parry*) action.
+	pub fn new_action(check: Quality) -> Box<dyn Action>
+	{
+		Combat::with_source(check,Default::default())
+	}
+
+	/// Creates the action rolling its dice with the given, possibly shared, source.
+	pub fn with_source(check: Quality, rng: Source) -> Box<dyn Action>
+	{
+		Box::new(Combat
+		{
+			check,
+			rng,
+		})
+	}
+
+	fn name(&self) -> &'static str
+	{
+		match self.check
+		{
+			Quality::Parry => "parry",
+			_ => "attack",
+		}
+	}
+}
+
+/// Rolls an attack or parry for a combat talent of the *Hero*, supports modifiers.
+///
+/// A 1 is confirmed by a second roll to be a critical success, a 20 is confirmed by a second roll to be a critical failure (Patzer).
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Quality;
+/// # use dsa::Hero;
+/// # use dsa::output::Output;
+/// # use dsa::commands::Combat;
+/// let mut parry = Combat::new_action(Quality::Parry);
+/// # let mut hero = Hero::default();
+/// hero.combat.insert("säbel".to_string(),(12,Some(10)));
+/// let matches = parry.usage().get_matches_from(&["parry","-m","2","Säbel"]);
+/// let output = parry.call(&hero,&matches).unwrap();
+/// assert_eq!(1,output.len());
+///
+/// if let Output::Combat { check, value, mods, die, confirmation, .. } = &output[0] {
+///     assert_eq!(Quality::Parry, *check);
+///     assert_eq!(10, *value);
+///     assert_eq!(2, *mods);
+///     assert_eq!(*die == 1 || *die == 20, confirmation.is_some());
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Combat
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new(self.name())
+			.about(match self.check
+			{
+				Quality::Parry => "roll a parry (PA) for a combat talent",
+				_ => "roll an attack (AT) for a combat talent",
+			})
+			.arg
+				( Arg::new("modifier")
+				.short('m')
+				.long("modifier")
+				.alias("mod")
+				.help("modification as positive (bad) or negative (good) integer")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.number_of_values(1)
+				)
+			.arg
+				( Arg::new("talent")
+				.value_name("TALENT")
+				.help("the combat talent to use")
+				.takes_value(true)
+				.required(true)
+				)
+	}
+
+	fn call(&mut self, hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let d20: Uniform<_> = (1..21).into();
+		let mut rng = self.rng.clone();
+
+		let mods = modifiers(matches, "modifier")?;
+
+		let talent = matches.value_of("talent").unwrap();
+		let (talent,(attack,parry)) = hero.combat_talent(talent)?;
+		let value = match self.check
+		{
+			Quality::Parry => parry.ok_or_else(|| ErrorKind::CannotParry(talent.to_string()))?,
+			_ => *attack,
+		};
+
+		let check = |die: isize| die == 1 || (die != 20 && die <= value - mods);
+		let die = d20.sample(&mut rng);
+		let confirmation = if die == 1 || die == 20 { Some(d20.sample(&mut rng)) } else { None };
+
+		let (success,critical) = match (die,confirmation)
+		{
+			(1,Some(confirmation)) => (true,check(confirmation)),
+			(20,Some(confirmation)) => (false,!check(confirmation)),
+			(die,_) => (check(die),false),
+		};
+
+		Ok(vec![Output::Combat
+		{
+			success,
+			critical,
+			talent: talent.to_string(),
+			check: self.check,
+			value,
+			mods,
+			die,
+			confirmation,
+		}])
+	}
+}
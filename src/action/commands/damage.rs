@@ -0,0 +1,118 @@
+use super::*;
+use super::roll::modifiers;
+
+pub struct Damage
+{
+	rng: Source,
+}
+
+impl Damage
+{
+	pub fn new_action() -> Box<dyn Action>
+	{
+		Damage::with_source(Default::default())
+	}
+
+	/// Creates the action rolling its dice with the given, possibly shared, source.
+	pub fn with_source(rng: Source) -> Box<dyn Action>
+	{
+		Box::new(Damage
+		{
+			rng,
+		})
+	}
+}
+
+/// Rolls the hit points (TP) of a weapon of the *Hero*, including the TP/KK bonus.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Quality::*;
+/// # use dsa::{Hero, Weapon};
+/// # use dsa::output::Output;
+/// # use dsa::commands::Damage;
+/// let mut damage = Damage::new_action();
+/// # let mut hero = Hero::default();
+/// hero.qualities.insert(Strength,16);
+/// hero.weapons.push(Weapon
+/// {
+///     name: "Säbel".to_string(),
+///     damage: "1W6+3".parse().unwrap(),
+///     strength: Some((12,4)),
+///     ..Default::default()
+/// });
+/// let matches = damage.usage().get_matches_from(&["damage","-b","2","säbel"]);
+/// let output = damage.call(&hero,&matches).unwrap();
+/// assert_eq!(1,output.len());
+///
+/// if let Output::Damage { dice, strength, bonus, total, .. } = &output[0] {
+///     assert_eq!(1, dice.len());
+///     assert_eq!(1, *strength);
+///     assert_eq!(2, *bonus);
+///     assert_eq!(dice[0] + 3 + 1 + 2, *total);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Damage
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("damage")
+			.about("roll the hit points (TP) of a weapon")
+			.arg
+				( Arg::new("bonus")
+				.short('b')
+				.long("bonus")
+				.help("additional hit points as positive or negative integer")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.number_of_values(1)
+				)
+			.arg
+				( Arg::new("no-strength")
+				.long("no-strength")
+				.help("do not apply the TP/KK bonus")
+				)
+			.arg
+				( Arg::new("weapon")
+				.value_name("WEAPON")
+				.help("the weapon to deal damage with")
+				.takes_value(true)
+				.required(true)
+				)
+	}
+
+	fn call(&mut self, hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let name = matches.value_of("weapon").unwrap();
+		let weapon = hero.weapon(name).ok_or_else(|| ErrorKind::UnknownWeapon(name.to_string()))?;
+
+		let bonus = modifiers(matches, "bonus")?;
+
+		let strength = if matches.is_present("no-strength")
+		{
+			0
+		}
+		else
+		{
+			weapon.strength_bonus(*hero.qualities.get(&Quality::Strength).unwrap_or(&0))
+		};
+
+		let dice = weapon.damage.roll(&mut self.rng);
+		let total = 0.max(weapon.damage.total(&dice) + strength + bonus);
+
+		Ok(vec![Output::Damage
+		{
+			weapon: weapon.name.clone(),
+			damage: weapon.damage,
+			dice,
+			strength,
+			bonus,
+			total,
+		}])
+	}
+}
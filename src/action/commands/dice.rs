@@ -0,0 +1,86 @@
+use super::*;
+use crate::dice::Expression;
+
+pub struct Dice
+{
+	rng: Source,
+}
+
+impl Dice
+{
+	pub fn new_action() -> Box<dyn Action>
+	{
+		Dice::with_source(Default::default())
+	}
+
+	/// Creates the action rolling its dice with the given, possibly shared, source.
+	pub fn with_source(rng: Source) -> Box<dyn Action>
+	{
+		Box::new(Dice
+		{
+			rng,
+		})
+	}
+}
+
+/// Rolls generic dice expressions like "3W6+4" or "2d20", independent of the *Hero*.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Hero;
+/// # use dsa::output::Output;
+/// # use dsa::commands::Dice;
+/// let mut dice = Dice::new_action();
+/// let matches = dice.usage().get_matches_from(&["dice","3W6+4","W20"]);
+/// # let hero = Hero::default();
+/// let output = dice.call(&hero,&matches).unwrap();
+/// assert_eq!(2,output.len());
+///
+/// if let Output::Dice { expression, dice, total } = &output[0] {
+///     assert_eq!("3W6+4", expression.to_string());
+///     assert_eq!(3, dice.len());
+///     assert_eq!(dice.iter().sum::<isize>() + 4, *total);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Dice
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("dice")
+			.about("roll arbitrary dice")
+			.arg
+				( Arg::new("expression")
+				.value_name("EXPRESSION")
+				.help("the dice to roll, e.g. 3W6+4 or 2d20")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.required(true)
+				)
+	}
+
+	fn call(&mut self, _: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let mut rng = self.rng.clone();
+
+		matches.values_of("expression")
+			.unwrap()
+			.map(|expression|
+			{
+				let expression = expression.parse::<Expression>()?;
+				let dice = expression.roll(&mut rng);
+
+				Ok(Output::Dice
+				{
+					total: expression.total(&dice),
+					expression,
+					dice,
+				})
+			})
+			.collect()
+	}
+}
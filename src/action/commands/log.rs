@@ -0,0 +1,78 @@
+use super::*;
+use crate::session::Session;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Lists the annotated history of all tracker changes within a session.
+///
+/// # Examples
+///
+/// ```
+/// # use std::{cell::RefCell, rc::Rc};
+/// # use dsa::commands::{Log, Tracker};
+/// # use dsa::output::Output;
+/// # use dsa::session::Session;
+/// # let mut hero = dsa::Hero::default();
+/// hero.health = 30;
+/// hero.stamina = 30;
+/// let session = Rc::new(RefCell::new(Session::new(&hero)));
+/// let mut health = Tracker::with_session("health",session.clone());
+/// let mut stamina = Tracker::with_session("stamina",session.clone());
+/// health.call(&hero,&health.usage().get_matches_from(&["health","--sub","6","--reason","orc axe"])).unwrap();
+/// stamina.call(&hero,&stamina.usage().get_matches_from(&["stamina","--sub","3"])).unwrap();
+///
+/// let mut log = Log::with_session(session);
+/// let output = log.call(&hero,&log.usage().get_matches_from(&["log","health"])).unwrap();
+/// assert_eq!(1,output.len());
+/// if let Output::Log(entry) = &output[0] {
+///     assert_eq!("health", entry.gauge);
+///     assert_eq!(24, entry.current.current);
+///     assert_eq!(Some("orc axe"), entry.reason.as_deref());
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+pub struct Log
+{
+	session: Rc<RefCell<Session>>,
+}
+
+impl Log
+{
+	pub fn with_session(session: Rc<RefCell<Session>>) -> Box<dyn Action>
+	{
+		Box::new(Log
+		{
+			session,
+		})
+	}
+}
+
+impl Action for Log
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("log")
+			.about("list the history of tracker changes")
+			.arg
+				( Arg::new("tracker")
+				.value_name("TRACKER")
+				.help("only list changes of these trackers")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				)
+	}
+
+	fn call(&mut self, _: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let trackers = matches.values_of("tracker").map(|trackers| trackers.collect::<Vec<_>>());
+
+		Ok(self.session.borrow().log.iter()
+			.filter(|entry| trackers.as_ref().map(|trackers| trackers.contains(&entry.gauge.as_str())).unwrap_or(true))
+			.cloned()
+			.map(Output::Log)
+			.collect())
+	}
+}
@@ -0,0 +1,201 @@
+use super::*;
+use super::roll::{evaluate, modifiers, stats};
+
+pub struct Odds;
+
+impl Odds
+{
+	pub fn new_action() -> Box<dyn Action>
+	{
+		Box::new(Odds)
+	}
+}
+
+/// Calculates the exact probabilities of a skill check of the *Hero*, supports modifiers.
+///
+/// All 8000 possible outcomes of the three dice are evaluated exactly like `roll` does, including the encumbrance (BE).
+/// With `--table` the probabilities and expected TaP* are listed for a range of modifiers instead, failures count as zero TaP*.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Quality::*;
+/// # use dsa::Hero;
+/// # use dsa::output::Output;
+/// # use dsa::commands::Odds;
+/// let mut odds = Odds::new_action();
+/// # let mut hero = Hero::default();
+/// hero.qualities.extend(
+///     vec![
+///         (Agility,10),
+///         (Dexterity,10),
+///         (Strength,10),
+///     ]
+/// );
+/// hero.skills.insert("bogen".to_string(),(0,[Agility,Dexterity,Strength]));
+/// let matches = odds.usage().get_matches_from(&["odds","bogen"]);
+/// let output = odds.call(&hero,&matches).unwrap();
+/// assert_eq!(1,output.len());
+///
+/// if let Output::Odds { success, critical_success, critical_failure, .. } = &output[0] {
+///     // every die must be at most 10, or at least two ones, but not two twenties
+///     assert_eq!(1000.0 / 8000.0 + 3.0 * 10.0 / 8000.0, *success);
+///     assert_eq!(58.0 / 8000.0, *critical_success);
+///     assert_eq!(58.0 / 8000.0, *critical_failure);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+///
+/// let matches = odds.usage().get_matches_from(&["odds","--table","--from","-2","--to","2","bogen"]);
+/// let output = odds.call(&hero,&matches).unwrap();
+///
+/// if let Output::OddsTable { rows, .. } = &output[0] {
+///     assert_eq!(vec![-2,-1,0,1,2], rows.iter().map(|(mods,_,_)| *mods).collect::<Vec<_>>());
+///     assert!(rows.windows(2).all(|rows| rows[0].1 >= rows[1].1));
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Odds
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("odds")
+			.about("calculate the probability of success for a skill")
+			.arg
+				( Arg::new("modifier")
+				.short('m')
+				.long("modifier")
+				.alias("mod")
+				.help("modification as positive (bad) or negative (good) integer")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.number_of_values(1)
+				)
+			.arg
+				( Arg::new("no-encumbrance")
+				.long("no-encumbrance")
+				.alias("no-be")
+				.help("do not add the encumbrance (BE) of the armor to the modifiers")
+				)
+			.arg
+				( Arg::new("table")
+				.long("table")
+				.short('t')
+				.help("list the odds for a range of modifiers")
+				.conflicts_with("modifier")
+				)
+			.arg
+				( Arg::new("from")
+				.long("from")
+				.help("lowest modifier of the table")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.default_value("-7")
+				.requires("table")
+				)
+			.arg
+				( Arg::new("to")
+				.long("to")
+				.help("highest modifier of the table")
+				.allow_hyphen_values(true)
+				.takes_value(true)
+				.default_value("15")
+				.requires("table")
+				)
+			.arg
+				( Arg::new("skill")
+				.value_name("SKILL")
+				.help("the skills to calculate")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.required(true)
+				)
+	}
+
+	fn call(&mut self, hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let mods = modifiers(matches, "modifier")?;
+
+		matches.values_of("skill")
+			.unwrap()
+			.map(|skill|
+			{
+				let (skill,(base,checks)) = hero.skill(skill)?;
+				let encumbrance = if matches.is_present("no-encumbrance") { 0 } else { hero.skill_encumbrance(skill) };
+				let stat = stats(hero, checks)?;
+
+				if matches.is_present("table")
+				{
+					let from = matches.value_of("from").unwrap().parse::<isize>()?;
+					let to = matches.value_of("to").unwrap().parse::<isize>()?;
+					return Ok(Output::OddsTable
+					{
+						skill: skill.to_string(),
+						base: *base,
+						encumbrance,
+						rows: (from..=to)
+							.map(|mods|
+							{
+								let (success,_,_,remainder) = probabilities(*base, mods + encumbrance, &stat);
+								(mods,success,remainder)
+							})
+							.collect(),
+					});
+				}
+
+				let (success,critical_success,critical_failure,_) = probabilities(*base, mods + encumbrance, &stat);
+
+				Ok(Output::Odds
+				{
+					skill: skill.to_string(),
+					base: *base,
+					mods,
+					encumbrance,
+					success,
+					critical_success,
+					critical_failure,
+				})
+			})
+			.collect()
+	}
+}
+
+/// Probabilities of success, critical success, and critical failure, as well as the expected TaP* of a skill check.
+fn probabilities(base: isize, mods: isize, stat: &[isize;3]) -> (f64,f64,f64,f64)
+{
+	let (mut success, mut critical_success, mut critical_failure, mut remainder) = (0, 0, 0, 0);
+	for first in 1..21
+	{
+		for second in 1..21
+		{
+			for third in 1..21
+			{
+				match evaluate(base, mods, stat, &[first,second,third])
+				{
+					(true,critical,result) =>
+					{
+						success += 1;
+						remainder += 0.max(result);
+						if critical
+						{
+							critical_success += 1;
+						}
+					},
+					(false,true,_) => critical_failure += 1,
+					(false,false,_) => {},
+				}
+			}
+		}
+	}
+
+	(
+		success as f64 / 8000.0,
+		critical_success as f64 / 8000.0,
+		critical_failure as f64 / 8000.0,
+		remainder as f64 / 8000.0,
+	)
+}
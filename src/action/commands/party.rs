@@ -0,0 +1,215 @@
+use super::*;
+use super::roll::{modifiers, roll};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The heroes of a party, shared by the actions acting on all of them.
+///
+/// The actions only access the party during their call, so it can be filled after creating them.
+/// Without a party, the actions act on the *Hero* passed to their call instead.
+#[derive(Debug,Clone,Default)]
+pub struct Roster(Rc<RefCell<Vec<Hero>>>);
+
+impl Roster
+{
+	/// Replaces the heroes of the party.
+	pub fn set(&self, heroes: Vec<Hero>)
+	{
+		*self.0.borrow_mut() = heroes;
+	}
+
+	/// Whether there is no party at all.
+	pub fn is_empty(&self) -> bool
+	{
+		self.0.borrow().is_empty()
+	}
+
+	/// The heroes of the party, or just the given *Hero* if there is no party.
+	pub fn heroes(&self, hero: &Hero) -> Vec<Hero>
+	{
+		match self.0.borrow()[..]
+		{
+			[] => vec![hero.clone()],
+			ref party => party.to_vec(),
+		}
+	}
+}
+
+impl From<Vec<Hero>> for Roster
+{
+	fn from(heroes: Vec<Hero>) -> Self
+	{
+		Roster(Rc::new(RefCell::new(heroes)))
+	}
+}
+
+pub struct Party
+{
+	party: Roster,
+	rng: Source,
+}
+
+impl Party
+{
+	/// Creates the action for a party, see *Roster*.
+	pub fn with_party(party: Roster, rng: Source) -> Box<dyn Action>
+	{
+		Box::new(Party
+		{
+			party,
+			rng,
+		})
+	}
+}
+
+/// Rolls the same check for every hero of the party, e.g. for perception.
+///
+/// Every hero having the skill rolls once, followed by a summary of who succeeded with the most TaP*, who lacks the skill, and who could not roll for it.
+/// The skill is looked up once among the skills of the whole party, so a prefix means the same skill for everyone.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Quality::*;
+/// # use dsa::Hero;
+/// # use dsa::output::Output;
+/// # use dsa::commands::{Party, Roster};
+/// let mut alrik = Hero::default();
+/// alrik.name = "Alrik".to_string();
+/// alrik.qualities.extend(vec![(Wisdom,12),(Intuition,14)]);
+/// alrik.skills.insert("sinnenschärfe".to_string(),(7,[Wisdom,Intuition,Intuition]));
+/// let mut gerda = Hero::default();
+/// gerda.name = "Gerda".to_string();
+/// gerda.skills.insert("sinnesschärfe".to_string(),(3,[Wisdom,Intuition,Intuition]));
+/// let mut hugo = Hero::default();
+/// hugo.name = "Hugo".to_string();
+/// hugo.skills.insert("sinnenschärfe".to_string(),(4,[Wisdom,Intuition,Intuition]));
+///
+/// let party = Roster::from(vec![alrik,gerda,hugo]);
+/// let mut command = Party::with_party(party,Default::default());
+/// let matches = command.usage().get_matches_from(&["party","roll","sinnenschaerfe"]);
+/// let output = command.call(&Hero::default(),&matches).unwrap();
+/// assert_eq!(2,output.len());
+///
+/// if let Output::Roll { hero, .. } = &output[0] {
+///     assert_eq!(Some("Alrik"), hero.as_deref());
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// if let Output::PartyRoll { skill, missing, failed, .. } = &output[1] {
+///     assert_eq!("sinnenschärfe", skill);
+///     assert_eq!(&vec!["Gerda".to_string()], missing);
+///     assert_eq!("Hugo", failed[0].0);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Party
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("party")
+			.about("act for all heroes of the party at once")
+			.subcommand_required(true)
+			.subcommand
+				( Command::new("roll")
+				.about("roll for a skill with every hero having it")
+				.arg
+					( Arg::new("modifier")
+					.short('m')
+					.long("modifier")
+					.alias("mod")
+					.help("modification as positive (bad) or negative (good) integer")
+					.allow_hyphen_values(true)
+					.takes_value(true)
+					.multiple_occurrences(true)
+					.number_of_values(1)
+					)
+				.arg
+					( Arg::new("no-encumbrance")
+					.long("no-encumbrance")
+					.alias("no-be")
+					.help("do not add the encumbrance (BE) of the armor to the modifiers")
+					)
+				.arg
+					( Arg::new("skill")
+					.value_name("SKILL")
+					.help("the skill to test")
+					.takes_value(true)
+					.required(true)
+					)
+				)
+	}
+
+	fn call(&mut self, hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let heroes = self.party.heroes(hero);
+
+		// there is only the one subcommand for now
+		let matches = matches.subcommand_matches("roll").unwrap();
+		let mods = modifiers(matches, "modifier")?;
+		let skill = matches.value_of("skill").unwrap();
+
+		// nobody knowing the skill is most likely a typo
+		let known = Hero
+		{
+			skills: heroes.iter().flat_map(|hero| hero.skills.clone()).collect(),
+			..Default::default()
+		};
+		let skill = known.skill(skill)?.0.to_string();
+
+		let mut outputs = vec![];
+		let mut missing = vec![];
+		let mut failed = vec![];
+		let mut best: Option<(isize,Vec<String>)> = None;
+		for hero in heroes.iter()
+		{
+			if !hero.skills.contains_key(&skill)
+			{
+				missing.push(hero.name.clone());
+				continue;
+			}
+
+			let mut output = match roll(hero, &skill, mods, !matches.is_present("no-encumbrance"), &mut self.rng)
+			{
+				Ok(output) => output,
+				Err(error) =>
+				{
+					failed.push((hero.name.clone(),error.to_string()));
+					continue;
+				},
+			};
+			if let Output::Roll { hero: ref mut tag, success, remainder, .. } = output
+			{
+				*tag = Some(hero.name.clone());
+				match best
+				{
+					_ if !success => {},
+					Some((most,ref mut names)) if most == remainder => names.push(hero.name.clone()),
+					Some((most,_)) if most > remainder => {},
+					_ => best = Some((remainder,vec![hero.name.clone()])),
+				}
+			}
+			outputs.push(output);
+		}
+
+		let (remainder,best) = match best
+		{
+			Some((remainder,best)) => (Some(remainder),best),
+			None => (None,vec![]),
+		};
+		outputs.push(Output::PartyRoll
+		{
+			skill,
+			best,
+			remainder,
+			missing,
+			failed,
+		});
+
+		Ok(outputs)
+	}
+}
@@ -1,19 +1,34 @@
 use super::*;
 
+use rand::Rng;
 use rand::distributions::{Distribution, Uniform};
 
-pub struct Roll;
+pub struct Roll
+{
+	rng: Source,
+}
 
 impl Roll
 {
 	pub fn new_action() -> Box<dyn Action>
 	{
-		Box::new(Roll)
+		Roll::with_source(Default::default())
+	}
+
+	/// Creates the action rolling its dice with the given, possibly shared, source.
+	pub fn with_source(rng: Source) -> Box<dyn Action>
+	{
+		Box::new(Roll
+		{
+			rng,
+		})
 	}
 }
 
 /// Rolls the dice for a certain skill of the *Hero*, supports modifiers.
 ///
+/// The effective encumbrance (BE) of the worn armor on the skill is added to the modifiers unless disabled.
+///
 /// # Examples
 ///
 /// ```
@@ -35,10 +50,11 @@ impl Roll
 /// let output = roll.call(&hero,&matches).unwrap();
 /// assert_eq!(1,output.len());
 /// 
-/// if let Output::Roll { base, stat, mods, .. } = &output[0] {
+/// if let Output::Roll { base, stat, mods, encumbrance, .. } = &output[0] {
 ///     assert_eq!(4, *base);
 ///     assert_eq!(&[1,2,3], stat);
 ///     assert_eq!(-2, *mods);
+///     assert_eq!(0, *encumbrance);
 /// }
 /// # else {
 /// #     panic!("unexpected output");
@@ -46,7 +62,7 @@ impl Roll
 /// ```
 impl Action for Roll
 {
-	fn usage<'a,'b>(&'a self) -> Command<'b>
+	fn usage<'b>(&self) -> Command<'b>
 	{
 		Command::new("roll")
 			.about("roll for a skill")
@@ -61,6 +77,12 @@ impl Action for Roll
 				.multiple_occurrences(true)
 				.number_of_values(1)
 				)
+			.arg
+				( Arg::new("no-encumbrance")
+				.long("no-encumbrance")
+				.alias("no-be")
+				.help("do not add the encumbrance (BE) of the armor to the modifiers")
+				)
 			.arg
 				( Arg::new("skill")
 				.value_name("SKILL")
@@ -73,67 +95,91 @@ impl Action for Roll
 
 	fn call(&mut self, hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
 	{
-		let d20: Uniform<_> = (1..21).into();
-		let mut rng = rand::thread_rng();
+		let mut rng = self.rng.clone();
+		let mods = modifiers(matches, "modifier")?;
 
 		matches.values_of("skill")
 			.unwrap()
-			.map(|skill|
-			{
-				let mods = matches.values_of("modifier")
-					.map(|mods| mods
-						.map(|modi| Ok(modi.parse()?))
-						.collect::<Result<Vec<isize>>>()
-						.map(|mods| mods.into_iter().sum())
-					)
-					.unwrap_or(Ok(0))?;
+			.map(|skill| roll(hero, skill, mods, !matches.is_present("no-encumbrance"), &mut rng))
+			.collect()
+	}
+}
 
-				// TODO: use custom error
-				let (base,values_enum) = hero.skills.get(&skill.to_lowercase()).ok_or_else(|| format!("unknown skill '{}'", skill))?;
+/// Rolls a skill check of the *Hero*, adding the encumbrance (BE) of the armor to the modifiers unless disabled.
+pub(crate) fn roll<R: Rng>(hero: &Hero, skill: &str, mods: isize, encumbered: bool, rng: &mut R) -> Result<Output>
+{
+	let d20: Uniform<_> = (1..21).into();
 
-				let values =
-				{
-					let mut iter = values_enum.iter()
-						.map(|value| -> Result<isize>
-						{
-							Ok(*hero.qualities.get(value).ok_or("cannot roll unknown quality")?)
-						});
-					[
-						iter.next().ok_or("quality retrieval is broken")??,
-						iter.next().ok_or("quality retrieval is broken")??,
-						iter.next().ok_or("quality retrieval is broken")??,
-					]
-				};
-				let rolls =
-				{
-					let mut iter = d20.sample_iter(&mut rng);
-					[
-						iter.next().ok_or("rng sampling is broken")?,
-						iter.next().ok_or("rng sampling is broken")?,
-						iter.next().ok_or("rng sampling is broken")?,
-					]
-				};
-				let num_20 = rolls.iter().filter(|i| **i == 20).count();
-				let num_1 = rolls.iter().filter(|i| **i == 1).count();
-				let result = 0.max(base - mods) + values.iter()
-					.map(|stat| stat + 0.min(base - mods))
-					.zip(&rolls)
-					.map(|(stat,die)| (stat-die).min(0))
-					.sum::<isize>();
+	let (skill,(base,values_enum)) = hero.skill(skill)?;
+	let encumbrance = if encumbered { hero.skill_encumbrance(skill) } else { 0 };
+	let total = mods + encumbrance;
 
-				Ok(Output::Roll
-				{
-					success: (num_20 < 2) && (result >= 0 || num_1 > 1),
-					critical: (num_20 > 1) || (num_1 > 1),
-					dice: rolls,
-					checks: *values_enum,
-					stat: values,
-					remainder: result,
-					base: *base,
-					mods,
-				})
-			})
-			.collect()
-	}
+	let values = stats(hero, values_enum)?;
+	let rolls =
+	{
+		let mut iter = d20.sample_iter(rng);
+		[
+			iter.next().ok_or("rng sampling is broken")?,
+			iter.next().ok_or("rng sampling is broken")?,
+			iter.next().ok_or("rng sampling is broken")?,
+		]
+	};
+	let (success,critical,result) = evaluate(*base, total, &values, &rolls);
+
+	Ok(Output::Roll
+	{
+		hero: None,
+		success,
+		critical,
+		dice: rolls,
+		checks: *values_enum,
+		stat: values,
+		remainder: result,
+		base: *base,
+		mods,
+		encumbrance,
+	})
 }
 
+/// Sum of all modifiers passed via the argument, e.g. `--modifier`.
+pub(crate) fn modifiers(matches: &ArgMatches, arg: &str) -> Result<isize>
+{
+	matches.values_of(arg)
+		.map(|mods| mods
+			.map(|modi| Ok(modi.parse()?))
+			.collect::<Result<Vec<isize>>>()
+			.map(|mods| mods.into_iter().sum())
+		)
+		.unwrap_or(Ok(0))
+}
+
+/// The values of the qualities to roll against.
+pub(crate) fn stats(hero: &Hero, checks: &[Quality;3]) -> Result<[isize;3]>
+{
+	let mut iter = checks.iter()
+		.map(|value| -> Result<isize>
+		{
+			Ok(*hero.qualities.get(value).ok_or_else(|| ErrorKind::MissingQuality(format!("{:?}", value)))?)
+		});
+	Ok([
+		iter.next().ok_or("quality retrieval is broken")??,
+		iter.next().ok_or("quality retrieval is broken")??,
+		iter.next().ok_or("quality retrieval is broken")??,
+	])
+}
+
+/// Evaluates the dice of a skill check, returning success, whether it was critical, and the remaining points.
+///
+/// Modifiers larger than the skill value reduce the stats by the difference.
+pub(crate) fn evaluate(base: isize, mods: isize, stat: &[isize;3], dice: &[isize;3]) -> (bool,bool,isize)
+{
+	let num_20 = dice.iter().filter(|i| **i == 20).count();
+	let num_1 = dice.iter().filter(|i| **i == 1).count();
+	let result = 0.max(base - mods) + stat.iter()
+		.map(|stat| stat + 0.min(base - mods))
+		.zip(dice)
+		.map(|(stat,die)| (stat-die).min(0))
+		.sum::<isize>();
+
+	((num_20 < 2) && (result >= 0 || num_1 > 1), (num_20 > 1) || (num_1 > 1), result)
+}
@@ -0,0 +1,173 @@
+use super::*;
+use crate::config::Config;
+use crate::session::{Gauge, Session};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use error_chain::bail;
+
+/// The trackers every hero has, these cannot be removed.
+const BUILTIN: [&str; 3] = ["health","astral","stamina"];
+
+/// Commands which exist only within the cli or only outside of it, custom trackers must not be named like them either.
+pub const RESERVED: [&str; 7] = ["cli","track","alias","use","exit","undo","redo"];
+
+/// Creates and removes custom trackers within a session, e.g. for arrows or rations.
+///
+/// The trackers themselves are just gauges in the *Session*, it is up to the caller to provide a *Tracker* for each of them.
+///
+/// # Examples
+///
+/// ```
+/// # use std::{cell::RefCell, rc::Rc};
+/// # use dsa::commands::Track;
+/// # use dsa::session::Session;
+/// # let hero = dsa::Hero::default();
+/// let session = Rc::new(RefCell::new(Session::new(&hero)));
+/// let mut track = Track::with_session(session.clone(),vec!["roll".to_string()]);
+/// track.call(&hero,&track.usage().get_matches_from(&["track","new","arrows","--max","20"])).unwrap();
+/// assert_eq!(20, session.borrow().gauges["arrows"].current);
+///
+/// assert!(track.call(&hero,&track.usage().get_matches_from(&["track","new","roll","--max","1"])).is_err());
+/// assert!(track.call(&hero,&track.usage().get_matches_from(&["track","remove","health"])).is_err());
+/// ```
+pub struct Track
+{
+	session: Rc<RefCell<Session>>,
+	reserved: Vec<String>,
+	config: Rc<RefCell<Config>>,
+}
+
+impl Track
+{
+	/// Creates the action, custom trackers must not be named like any of the *reserved* commands.
+	pub fn with_session(session: Rc<RefCell<Session>>,reserved: Vec<String>) -> Box<dyn Action>
+	{
+		Track::with_config(session,reserved,Default::default())
+	}
+
+	/// Creates the action, custom trackers must not be named like the aliases of the *config* either.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use std::{cell::RefCell, rc::Rc};
+	/// # use dsa::commands::Track;
+	/// # use dsa::config::Config;
+	/// # use dsa::session::Session;
+	/// # let hero = dsa::Hero::default();
+	/// let config: Config = r#"{ "aliases": { "heal": "health --add 5" } }"#.parse().unwrap();
+	/// let session = Rc::new(RefCell::new(Session::new(&hero)));
+	/// let mut track = Track::with_config(session,vec![],Rc::new(RefCell::new(config)));
+	/// assert!(track.call(&hero,&track.usage().get_matches_from(&["track","new","heal","--max","3"])).is_err());
+	/// ```
+	pub fn with_config(session: Rc<RefCell<Session>>,reserved: Vec<String>,config: Rc<RefCell<Config>>) -> Box<dyn Action>
+	{
+		Box::new(Track
+		{
+			session,
+			reserved,
+			config,
+		})
+	}
+}
+
+impl Action for Track
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("track")
+			.about("manage custom trackers")
+			.subcommand_required(true)
+			.subcommand
+				( Command::new("new")
+				.about("create a new tracker")
+				.arg
+					( Arg::new("name")
+					.value_name("NAME")
+					.help("the name of the tracker")
+					.takes_value(true)
+					.required(true)
+					)
+				.arg
+					( Arg::new("max")
+					.long("max")
+					.short('m')
+					.help("the max value")
+					.takes_value(true)
+					.required(true)
+					)
+				.arg
+					( Arg::new("current")
+					.long("current")
+					.short('c')
+					.help("the current value (default: max)")
+					.takes_value(true)
+					)
+				)
+			.subcommand
+				( Command::new("remove")
+				.about("remove a custom tracker")
+				.arg
+					( Arg::new("name")
+					.value_name("NAME")
+					.help("the name of the tracker")
+					.takes_value(true)
+					.required(true)
+					)
+				)
+	}
+
+	fn call(&mut self, _: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let mut session = self.session.borrow_mut();
+
+		match matches.subcommand()
+		{
+			Some(("new", matches)) =>
+			{
+				let name = matches.value_of("name").unwrap();
+				if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) || self.reserved.iter().any(|reserved| reserved == name)
+				{
+					bail!("invalid tracker name '{}'", name);
+				}
+				if session.gauges.contains_key(name)
+				{
+					bail!("tracker '{}' already exists", name);
+				}
+				// the alias would be run instead of the tracker
+				if self.config.borrow().aliases.contains_key(name)
+				{
+					bail!("tracker '{}' would be hidden by the alias of the same name", name);
+				}
+				let max = 0.max(matches.value_of("max").unwrap().parse::<isize>()?);
+				let current = matches.value_of("current").map(str::parse::<isize>).transpose()?.unwrap_or(max);
+				let gauge = Gauge
+				{
+					current: current.max(0).min(max),
+					max,
+				};
+				session.gauges.insert(name.to_string(),gauge);
+
+				Ok(vec![Output::Gauge
+				{
+					name: name.to_string(),
+					current: gauge.current,
+					max: gauge.max,
+				}])
+			},
+			Some(("remove", matches)) =>
+			{
+				let name = matches.value_of("name").unwrap();
+				if BUILTIN.contains(&name)
+				{
+					bail!("cannot remove built-in tracker '{}'", name);
+				}
+				session.gauges.remove(name).ok_or_else(|| format!("unknown tracker '{}'", name))?;
+				Ok(vec![])
+			},
+			_ => unreachable!(),
+		}
+	}
+}
@@ -1,4 +1,8 @@
 use super::*;
+use crate::session::{Gauge, Session};
+
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// A generic tracker for a property during a cli session.
 ///
@@ -21,31 +25,53 @@ use super::*;
 /// #     panic!("unexpected output");
 /// # }
 /// ```
-pub struct Tracker<'a>
+///
+/// Trackers sharing a *Session* operate on the very same gauges:
+///
+/// ```
+/// # use std::{cell::RefCell, rc::Rc};
+/// # use dsa::commands::Tracker;
+/// # use dsa::session::Session;
+/// # let mut hero = dsa::Hero::default();
+/// hero.health = 30;
+/// let session = Rc::new(RefCell::new(Session::new(&hero)));
+/// let mut health = Tracker::with_session("health",session.clone());
+/// let matches = health.usage().get_matches_from(&["health","--sub","4"]);
+/// health.call(&hero,&matches).unwrap();
+/// assert_eq!(26, session.borrow().gauges["health"].current);
+/// ```
+pub struct Tracker
 {
-	name: &'a str,
-	max: isize,
-	current: isize,
+	name: String,
+	session: Rc<RefCell<Session>>,
 }
 
-impl<'a> Tracker<'a>
+impl Tracker
 {
-	pub fn new_action(name: &'static str,current: isize,max: isize) -> Box<dyn Action>
+	pub fn new_action<S: Into<String>>(name: S,current: isize,max: isize) -> Box<dyn Action>
+	{
+		let name = name.into();
+		let mut session = Session::default();
+		session.gauges.insert(name.clone(),Gauge { current, max });
+		Tracker::with_session(name,Rc::new(RefCell::new(session)))
+	}
+
+	/// Creates a tracker for the gauge of the given name within a shared session.
+	pub fn with_session<S: Into<String>>(name: S,session: Rc<RefCell<Session>>) -> Box<dyn Action>
 	{
 		Box::new(Tracker
 		{
-			name,
-			max,
-			current,
+			name: name.into(),
+			session,
 		})
 	}
 }
 
-impl Action for Tracker<'_>
+impl Action for Tracker
 {
-	fn usage<'a,'b>(&'a self) -> Command<'b>
+	fn usage<'b>(&self) -> Command<'b>
 	{
-		Command::new(self.name)
+		Command::new(self.name.clone())
 			.about("track the current value")
 			.arg
 				( Arg::new("get")
@@ -78,6 +104,14 @@ impl Action for Tracker<'_>
 				.short('m')
 				.help("change max value instead of current")
 				)
+			.arg
+				( Arg::new("reason")
+				.long("reason")
+				.short('r')
+				.value_name("REASON")
+				.help("note why the value changed, e.g. \"orc axe\"")
+				.takes_value(true)
+				)
 			.group
 				( ArgGroup::new("action")
 				. args(&["get","set","sub","add"])
@@ -86,9 +120,12 @@ impl Action for Tracker<'_>
 
 	fn call(&mut self, _: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>
 	{
+		let mut session = self.session.borrow_mut();
+		let mut gauge = session.gauges.get(&self.name).copied().unwrap_or_default();
+
 		if matches.is_present("action") && !matches.is_present("get")
 		{
-			let target = if matches.is_present("max") { &mut self.max } else { &mut self.current };
+			let target = if matches.is_present("max") { &mut gauge.max } else { &mut gauge.current };
 			*target = match [matches.value_of("set"),matches.value_of("add"),matches.value_of("sub")]
 			{
 				[Some(value),None,None] => value.parse::<isize>()?,
@@ -99,14 +136,15 @@ impl Action for Tracker<'_>
 		}
 
 		// keep it within bounds 0 <= current <= max
-		self.max = self.max.max(0);
-		self.current = self.current.max(0).min(self.max);
+		gauge.max = gauge.max.max(0);
+		gauge.current = gauge.current.max(0).min(gauge.max);
+		session.update(&self.name, gauge, matches.value_of("reason").map(str::to_string));
 
 		Ok(vec![Output::Gauge
 		{
-			name: self.name.to_string(),
-			current: self.current,
-			max: self.max,
+			name: self.name.clone(),
+			current: gauge.current,
+			max: gauge.max,
 		}])
 	}
 }
@@ -0,0 +1,120 @@
+use super::*;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// A hero file, or a hero within it, which failed to load.
+#[derive(Debug)]
+pub struct Failure
+{
+	/// The hero file.
+	pub path: PathBuf,
+	/// Why loading failed.
+	pub error: Error,
+}
+
+pub struct Validate
+{
+	party: Roster,
+	failures: Rc<RefCell<Vec<Failure>>>,
+}
+
+impl Validate
+{
+	pub fn new_action() -> Box<dyn Action>
+	{
+		Validate::with_party(Default::default())
+	}
+
+	/// Creates the action checking a whole party, see *Roster*.
+	pub fn with_party(party: Roster) -> Box<dyn Action>
+	{
+		Validate::with_failures(party,Default::default())
+	}
+
+	/// Creates the action for a party along with the hero files which failed to load.
+	pub fn with_failures(party: Roster, failures: Rc<RefCell<Vec<Failure>>>) -> Box<dyn Action>
+	{
+		Box::new(Validate
+		{
+			party,
+			failures,
+		})
+	}
+}
+
+/// Lists the parts of the hero files which could not be parsed, see *Hero::warnings*, as well as the files which failed to load at all.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Hero;
+/// # use dsa::output::Output;
+/// # use dsa::commands::Validate;
+/// let hero = r#"<helden><held name="Alrik">
+///     <eigenschaften>
+///         <eigenschaft name="Mut" value="12"/>
+///         <eigenschaft name="Klugheit" value="12"/>
+///     </eigenschaften>
+///     <talentliste>
+///         <talent name="Sinnenschärfe" probe=" (KL/IN/IN)" value="7"/>
+///         <talent name="Schleichen" value="5"/>
+///         <talent name="Klettern" probe=" (MU/GE/XX)" value="3"/>
+///     </talentliste>
+/// </held></helden>"#.parse::<Hero>().unwrap();
+///
+/// let mut validate = Validate::new_action();
+/// let output = validate.call(&hero,&validate.usage().get_matches_from(&["validate"])).unwrap();
+///
+/// if let Output::Validation { warnings, .. } = &output[0] {
+///     assert_eq!(&vec![
+///         "skill 'sinnenschärfe' rolls on Intuition which the hero lacks".to_string(),
+///         "skill 'schleichen' skipped: missing element 'helden/held[@name='Alrik']/talentliste/talent[@name='Schleichen']/@probe'".to_string(),
+///         "skill 'klettern' skipped: malformed probe ' (MU/GE/XX)' of skill 'klettern'".to_string(),
+///     ], warnings);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
+impl Action for Validate
+{
+	fn usage<'b>(&self) -> Command<'b>
+	{
+		Command::new("validate")
+			.alias("lint")
+			.about("list the parts of the hero files which could not be parsed")
+	}
+
+	fn call(&mut self, hero: &Hero, _: &ArgMatches) -> Result<Vec<Output>>
+	{
+		let failures = self.failures.borrow();
+		// only the files which failed to load are left to report without a party
+		let heroes = if self.party.is_empty() && !failures.is_empty() { vec![] } else { self.party.heroes(hero) };
+
+		let mut outputs: Vec<Output> = heroes.into_iter()
+			.map(|hero| Output::Validation
+			{
+				hero: hero.name.clone(),
+				warnings: hero.warnings.clone(),
+			})
+			.collect();
+		for failure in failures.iter()
+		{
+			let file = failure.path.display().to_string();
+			let message = failure.error.iter().map(ToString::to_string).collect::<Vec<_>>().join(": ");
+			// several heroes of one file may have failed
+			match outputs.last_mut()
+			{
+				Some(Output::Validation { hero, warnings }) if *hero == file => warnings.push(message),
+				_ => outputs.push(Output::Validation
+				{
+					hero: file,
+					warnings: vec![message],
+				}),
+			}
+		}
+		Ok(outputs)
+	}
+}
@@ -1,3 +1,4 @@
+use crate::dice::Source;
 use crate::error::*;
 use crate::hero::*;
 use crate::output::Output;
@@ -12,7 +13,7 @@ pub trait Action
 	/// Command line argument definition of the subcommand of the action.
 	///
 	/// This *App* determines determines the *ArgMatches* of the `call()` method.
-	fn usage<'a,'b>(&'a self) -> Command<'b>;
+	fn usage<'b>(&self) -> Command<'b>;
 	/// A method which maps self, as well as the current *Hero* and the result of the `usage()`-invocation into zero or more *Output*s.
 	fn call(&mut self,hero: &Hero, matches: &ArgMatches) -> Result<Vec<Output>>;
 }
@@ -27,12 +28,30 @@ pub mod commands
 {
 	use super::*;
 
+	mod check;
+	pub use check::Check;
 	mod cli;
 	pub use cli::Cli;
+	mod combat;
+	pub use combat::Combat;
+	mod damage;
+	pub use damage::Damage;
+	mod dice;
+	pub use dice::Dice;
+	mod log;
+	pub use log::Log;
+	mod odds;
+	pub use odds::Odds;
+	mod party;
+	pub use party::{Party, Roster};
 	mod roll;
 	pub use roll::Roll;
+	mod track;
+	pub use track::{Track, RESERVED};
 	mod tracker;
 	pub use tracker::Tracker;
+	mod validate;
+	pub use validate::{Failure, Validate};
 
 	/// Simply dumps the hero, ready to be displayed by the used *Formatter*.
 	///
@@ -49,7 +68,7 @@ pub mod commands
 
 	impl Action for Dump
 	{
-		fn usage<'a,'b>(&'a self) -> Command<'b>
+		fn usage<'b>(&self) -> Command<'b>
 		{
 			Command::new("dump")
 				.about("dump hero information")
@@ -57,7 +76,7 @@ pub mod commands
 
 		fn call(&mut self,hero: &Hero,_: &ArgMatches) -> Result<Vec<Output>>
 		{
-			Ok(vec![Output::Dump(hero.clone())])
+			Ok(vec![Output::Dump(Box::new(hero.clone()))])
 		}
 	}
 }
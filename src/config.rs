@@ -0,0 +1,167 @@
+//! User configuration shared by all sessions.
+//!
+//! The configuration is a JSON file, by default `$XDG_CONFIG_HOME/dsa-cli/config.json`, which can be overridden using the `DSACLI_CONFIG` environment variable.
+//! A missing file is equivalent to an empty configuration.
+//!
+//! Aliases of the cli are kept in the configuration file as well, see *Config::save_aliases*.
+//!
+//! The input history of the cli is kept in `$XDG_DATA_HOME/dsa-cli/history` unless `history` names another file or is `false`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use dsa::Hero;
+//! # use dsa::config::Config;
+//! # use dsa::session::Session;
+//! let config = r#"{ "trackers": { "arrows": 20, "karma": 24 } }"#.parse::<Config>().unwrap();
+//! assert_eq!(Some(&20), config.trackers.get("arrows"));
+//!
+//! let mut session = Session::new(&Hero::default());
+//! config.apply(&mut session);
+//! assert_eq!(24, session.gauges["karma"].current);
+//!
+//! let config = r#"{ "history": false, "aliases": { "heal": "health --add 5" } }"#.parse::<Config>().unwrap();
+//! assert_eq!(None, config.history);
+//! assert_eq!("health --add 5", config.aliases["heal"]);
+//! ```
+
+use crate::error::*;
+use crate::session::{Gauge, Session};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings read from the configuration file.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Config
+{
+	/// Additional trackers, as a map of name to max value.
+	pub trackers: BTreeMap<String,isize>,
+	/// The file to keep the input history of the cli in, `None` disables the history.
+	pub history: Option<PathBuf>,
+	/// Shortcuts of the cli, as a map of name to the command line they expand to.
+	pub aliases: BTreeMap<String,String>,
+}
+
+impl Default for Config
+{
+	fn default() -> Self
+	{
+		Config
+		{
+			trackers: BTreeMap::new(),
+			history: Config::default_history_path(),
+			aliases: BTreeMap::new(),
+		}
+	}
+}
+
+impl Config
+{
+	/// The history file in the user's data directory.
+	pub fn default_history_path() -> Option<PathBuf>
+	{
+		let base = std::env::var_os("XDG_DATA_HOME")
+			.filter(|dir| !dir.is_empty())
+			.map(PathBuf::from)
+			.or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))?;
+		Some(base.join("dsa-cli").join("history"))
+	}
+
+	/// The configuration file, either from `DSACLI_CONFIG` or in the user's configuration directory.
+	pub fn default_path() -> Option<PathBuf>
+	{
+		if let Some(path) = std::env::var_os("DSACLI_CONFIG").filter(|path| !path.is_empty())
+		{
+			return Some(PathBuf::from(path));
+		}
+		let base = std::env::var_os("XDG_CONFIG_HOME")
+			.filter(|dir| !dir.is_empty())
+			.map(PathBuf::from)
+			.or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+		Some(base.join("dsa-cli").join("config.json"))
+	}
+
+	/// Reads the configuration from the default path, a missing file yields the default configuration.
+	pub fn load_default() -> Result<Self>
+	{
+		match Config::default_path()
+		{
+			Some(ref path) if path.exists() => Config::load(path),
+			_ => Ok(Config::default()),
+		}
+	}
+
+	/// Reads the configuration from a file.
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self>
+	{
+		let path = path.as_ref();
+		fs::read_to_string(path)
+			.chain_err(|| format!("loading config file '{}'", path.display()))?
+			.parse::<Config>()
+			.chain_err(|| format!("failed parsing config file '{}'", path.display()))
+	}
+
+	/// Writes the aliases to the configuration file, keeping all other settings of the file as they are.
+	pub fn save_aliases<P: AsRef<Path>>(&self, path: P) -> Result<()>
+	{
+		let path = path.as_ref();
+		let mut document = match fs::read_to_string(path)
+		{
+			Ok(text) => ::json::parse(&text).chain_err(|| format!("failed parsing config file '{}'", path.display()))?,
+			Err(ref error) if error.kind() == std::io::ErrorKind::NotFound => ::json::JsonValue::new_object(),
+			Err(error) => return Err(error).chain_err(|| format!("loading config file '{}'", path.display())),
+		};
+		let mut aliases = ::json::JsonValue::new_object();
+		for (name,expansion) in &self.aliases
+		{
+			aliases[name.as_str()] = expansion.as_str().into();
+		}
+		document["aliases"] = aliases;
+
+		if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty())
+		{
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(path, format!("{}\n", document.pretty(2))).chain_err(|| format!("writing config file '{}'", path.display()))?;
+		Ok(())
+	}
+
+	/// Adds all configured trackers missing in the session, filled up to their max value.
+	pub fn apply(&self, session: &mut Session)
+	{
+		for (name,max) in &self.trackers
+		{
+			session.gauges.entry(name.to_string()).or_insert_with(|| Gauge::full(*max));
+		}
+	}
+}
+
+impl std::str::FromStr for Config
+{
+	type Err = Error;
+
+	fn from_str(input: &str) -> Result<Self>
+	{
+		let document = ::json::parse(input).chain_err(|| "config is not valid json")?;
+		let trackers = document["trackers"].entries()
+			.map(|(name,max)| Ok((name.to_string(),max.as_isize().ok_or_else(|| format!("tracker '{}' lacks a max value", name))?)))
+			.collect::<Result<_>>()?;
+		let aliases = document["aliases"].entries()
+			.map(|(name,expansion)| Ok((name.to_string(),expansion.as_str().ok_or_else(|| format!("alias '{}' is not a command line", name))?.to_string())))
+			.collect::<Result<_>>()?;
+		let history = match &document["history"]
+		{
+			::json::JsonValue::Null => Config::default_history_path(),
+			::json::JsonValue::Boolean(false) => None,
+			history => Some(history.as_str().map(PathBuf::from).ok_or("history must be a file name or false")?),
+		};
+		Ok(Config
+		{
+			trackers,
+			history,
+			aliases,
+		})
+	}
+}
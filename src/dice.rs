@@ -0,0 +1,217 @@
+//! Dice expressions in the usual DSA notation.
+//!
+//! Expressions like "3W6+4", "2d20", "1W6-1" or "W20" can be parsed into an *Expression* and rolled using any random number generator.
+//!
+//! # Examples
+//!
+//! ```
+//! # use dsa::dice::Expression;
+//! let expression = "3W6+4".parse::<Expression>().unwrap();
+//! assert_eq!(3, expression.count);
+//! assert_eq!(6, expression.sides);
+//! assert_eq!(4, expression.modifier);
+//!
+//! let dice = expression.roll(&mut rand::thread_rng());
+//! assert_eq!(3, dice.len());
+//! assert!(dice.iter().all(|die| (1..=6).contains(die)));
+//! assert_eq!(dice.iter().sum::<isize>() + 4, expression.total(&dice));
+//! ```
+
+use crate::error::*;
+
+use error_chain::bail;
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand::distributions::{Distribution, Uniform};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Upper bound for the number of dice rolled by a single *Expression*.
+pub const MAX_COUNT: isize = 1000;
+
+/// A number of dice with the same number of sides plus a constant modifier.
+///
+/// The type implements *std::str::FromStr* accepting both the German "W" and the English "d", the count defaults to one.
+/// *std::fmt::Display* always uses the German notation.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::dice::Expression;
+/// assert_eq!("1W20", "W20".parse::<Expression>().unwrap().to_string());
+/// assert_eq!("2W20", "2d20".parse::<Expression>().unwrap().to_string());
+/// assert_eq!("1W6-1", " 1w6 - 1 ".parse::<Expression>().unwrap().to_string());
+/// assert!("3W".parse::<Expression>().is_err());
+/// assert!("W0".parse::<Expression>().is_err());
+/// assert!("W6+".parse::<Expression>().is_err());
+/// ```
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub struct Expression
+{
+	/// Number of dice to roll
+	pub count: isize,
+	/// Number of sides of each die
+	pub sides: isize,
+	/// Constant added to the sum of the dice
+	pub modifier: isize,
+}
+
+impl Expression
+{
+	/// Rolls every die of the expression, the modifier is not applied.
+	pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<isize>
+	{
+		let die: Uniform<_> = (1..self.sides.max(1) + 1).into();
+		die.sample_iter(rng)
+			.take(self.count.clamp(0, MAX_COUNT) as usize)
+			.collect()
+	}
+
+	/// Sum of the dice plus the modifier.
+	pub fn total(&self, dice: &[isize]) -> isize
+	{
+		dice.iter().sum::<isize>() + self.modifier
+	}
+}
+
+impl Default for Expression
+{
+	fn default() -> Self
+	{
+		Expression
+		{
+			count: 1,
+			sides: 6,
+			modifier: 0,
+		}
+	}
+}
+
+impl std::str::FromStr for Expression
+{
+	type Err = Error;
+
+	fn from_str(input: &str) -> Result<Self>
+	{
+		let expression: String = input.chars()
+			.filter(|c| !c.is_whitespace())
+			.flat_map(char::to_lowercase)
+			.map(|c| if c == 'd' { 'w' } else { c })
+			.collect();
+		let (count, rest) = match expression.split_once('w')
+		{
+			Some(split) => split,
+			None => bail!("malformed dice expression '{}'", input),
+		};
+		let (sides, modifier) = match rest.find(['+', '-'])
+		{
+			Some(index) => (&rest[..index], Some(rest[index..].trim_start_matches('+'))),
+			None => (rest, None),
+		};
+		let expression = Expression
+		{
+			count: if count.is_empty() { 1 } else { count.parse()? },
+			sides: sides.parse()?,
+			modifier: modifier.map(str::parse).transpose()?.unwrap_or(0),
+		};
+		if !(1..=MAX_COUNT).contains(&expression.count) || expression.sides < 1
+		{
+			bail!("dice expression '{}' out of range", input);
+		}
+		Ok(expression)
+	}
+}
+
+impl std::fmt::Display for Expression
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		write!(f, "{}W{}", self.count, self.sides)?;
+		if self.modifier != 0
+		{
+			write!(f, "{:+}", self.modifier)?;
+		}
+		Ok(())
+	}
+}
+
+/// Shared source of randomness for all dice rolling *Action*s.
+///
+/// Cloning a *Source* yields a handle to the very same generator, so reseeding one handle reseeds all of them.
+/// The default source is seeded from the operating system, a seeded one replays the exact same dice.
+/// Seeded sources use ChaCha8, so a seed keeps replaying the same dice after updating the dependencies.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::dice::{Expression, Source};
+/// let expression = "3W20".parse::<Expression>().unwrap();
+/// let source = Source::seeded(42);
+/// let first = expression.roll(&mut source.clone());
+/// source.reseed(42);
+/// assert_eq!(first, expression.roll(&mut source.clone()));
+/// ```
+#[derive(Clone)]
+pub struct Source(Rc<RefCell<Box<dyn RngCore>>>);
+
+impl Source
+{
+	/// Wraps an arbitrary generator.
+	pub fn new<R: RngCore + 'static>(rng: R) -> Self
+	{
+		Source(Rc::new(RefCell::new(Box::new(rng))))
+	}
+
+	/// A deterministic generator for the given seed.
+	pub fn seeded(seed: u64) -> Self
+	{
+		Source::new(ChaCha8Rng::seed_from_u64(seed))
+	}
+
+	/// Replaces the generator of all handles by a deterministic one for the given seed.
+	pub fn reseed(&self, seed: u64)
+	{
+		*self.0.borrow_mut() = Box::new(ChaCha8Rng::seed_from_u64(seed));
+	}
+}
+
+impl Default for Source
+{
+	fn default() -> Self
+	{
+		Source::new(ChaCha8Rng::from_entropy())
+	}
+}
+
+impl std::fmt::Debug for Source
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.write_str("Source")
+	}
+}
+
+impl RngCore for Source
+{
+	fn next_u32(&mut self) -> u32
+	{
+		self.0.borrow_mut().next_u32()
+	}
+
+	fn next_u64(&mut self) -> u64
+	{
+		self.0.borrow_mut().next_u64()
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8])
+	{
+		self.0.borrow_mut().fill_bytes(dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error>
+	{
+		self.0.borrow_mut().try_fill_bytes(dest)
+	}
+}
@@ -1,6 +1,26 @@
 //! Error chain created using [error-chain](https://crates.io/crates/error-chain).
 //!
 //! It can be chained with the very same crate if desired for error handling.
+//! The *ErrorKind* tells the typical problems apart, so callers can react to them without parsing messages.
+//!
+//! # Examples
+//!
+//! ```
+//! # use dsa::Hero;
+//! # use dsa::error::ErrorKind;
+//! let mut hero = Hero::default();
+//! hero.skills.insert("schleichen".to_string(),(5,[dsa::Quality::Courage;3]));
+//!
+//! match hero.skill("schleichn").unwrap_err().kind()
+//! {
+//!     ErrorKind::UnknownSkill(name,suggestions) =>
+//!     {
+//!         assert_eq!("schleichn", name);
+//!         assert_eq!(&vec!["schleichen".to_string()], suggestions);
+//!     },
+//!     kind => panic!("unexpected error {:?}", kind),
+//! }
+//! ```
 
 use error_chain::error_chain;
 
@@ -21,6 +41,69 @@ error_chain! {
 
 	errors
 	{
+		/// A skill or combat talent the hero does not have, along with similar names.
+		UnknownSkill(name: String, suggestions: Vec<String>)
+		{
+			description("unknown skill")
+			display("unknown skill '{}'{}", name, match suggestions.len()
+			{
+				0 => String::new(),
+				_ => format!(", did you mean {}?", suggestions.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(" or ")),
+			})
+		}
+		/// A prefix matching several skills or combat talents.
+		AmbiguousSkill(name: String, candidates: Vec<String>)
+		{
+			description("ambiguous skill")
+			display("ambiguous skill '{}', could be {}", name, candidates.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(", "))
+		}
+		/// A name matching none of the heroes loaded, along with the names of all of them.
+		UnknownHero(name: String, names: Vec<String>)
+		{
+			description("unknown hero")
+			display("no hero named '{}', there are {}", name, names.join(", "))
+		}
+		/// A prefix matching several heroes.
+		AmbiguousHero(name: String, candidates: Vec<String>)
+		{
+			description("ambiguous hero")
+			display("ambiguous hero '{}', could be {}", name, candidates.join(", "))
+		}
+		/// A weapon the hero does not carry.
+		UnknownWeapon(name: String)
+		{
+			description("unknown weapon")
+			display("unknown weapon '{}'", name)
+		}
+		/// A combat talent which does not allow parrying, e.g. a ranged one.
+		CannotParry(talent: String)
+		{
+			description("cannot parry")
+			display("cannot parry with '{}'", talent)
+		}
+		/// A name which does not denote any quality.
+		UnknownQuality(name: String)
+		{
+			description("unknown quality")
+			display("unknown quality '{}'", name)
+		}
+		/// A quality which is needed for a check but missing on the hero.
+		MissingQuality(name: String)
+		{
+			description("missing quality")
+			display("the hero lacks the quality '{}'", name)
+		}
+		/// The qualities to roll a skill on (probe) could not be parsed.
+		MalformedProbe(skill: String, probe: String)
+		{
+			description("malformed probe")
+			display("malformed probe '{}' of skill '{}'", probe, skill)
+		}
+		/// An element or attribute missing in the xml, the path is like `helden/held[@name='Alrik']/eigenschaften`.
+		MissingElement(path: String)
+		{
+			description("missing element")
+			display("missing element '{}'", path)
+		}
 	}
 }
-
@@ -1,8 +1,9 @@
 use crate::error::*;
+use crate::dice;
 
 use error_chain::bail;
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 /// Public container for the hero attributes.
 ///
@@ -33,31 +34,697 @@ pub struct Hero
 	pub qualities: HashMap<Quality,isize>,
 	/// All skills documented in the xml, as a map of name to skill-level and attributes to roll on
 	pub skills: HashMap<String,(isize,[Quality;3])>,
+	/// How the encumbrance (BE) of the armor affects a skill, as a map of name to encumbrance
+	pub skill_encumbrance: HashMap<String,Encumbrance>,
+	/// Combat talents, as a map of name to attack (AT) and parry (PA) value, ranged talents do not have a parry value
+	pub combat: HashMap<String,(isize,Option<isize>)>,
+	/// Advantages and disadvantages (Vorteile/Nachteile) in the order of the xml
+	pub advantages: Vec<Advantage>,
+	/// Special abilities (Sonderfertigkeiten) in the order of the xml
+	pub special_abilities: Vec<SpecialAbility>,
+	/// Melee weapons (Nahkampfwaffen) carried by the hero
+	pub weapons: Vec<Weapon>,
+	/// Ranged weapons (Fernkampfwaffen) carried by the hero
+	pub ranged_weapons: Vec<Weapon>,
+	/// Armor (Rüstungen) worn by the hero
+	pub armor: Vec<Armor>,
+	/// Parts of the xml which could not be parsed and were skipped, e.g. a skill without a valid probe
+	pub warnings: Vec<String>,
 }
 
-impl std::str::FromStr for Hero
+impl Hero
+{
+	/// All special abilities matching the name, see *SpecialAbility::matches*.
+	pub fn special_abilities<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a SpecialAbility> + 'a
+	{
+		self.special_abilities.iter()
+			.filter(move |ability| ability.matches(name))
+	}
+
+	/// Looks up a skill by name, returning its canonical name along with its value and qualities.
+	///
+	/// The lookup is case insensitive, ignores the spelling of umlauts (e.g. "sinnenschaerfe"), and accepts unique prefixes.
+	/// Unknown skills yield an error suggesting similar names.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::Quality::*;
+	/// # use dsa::Hero;
+	/// let mut hero = Hero::default();
+	/// hero.skills.insert("sinnenschärfe".to_string(),(7,[Wisdom,Intuition,Intuition]));
+	/// hero.skills.insert("schleichen".to_string(),(5,[Courage,Intuition,Agility]));
+	/// hero.skills.insert("schwimmen".to_string(),(2,[Agility,Constitution,Strength]));
+	///
+	/// assert_eq!("sinnenschärfe", hero.skill("Sinnenschaerfe").unwrap().0);
+	/// assert_eq!("sinnenschärfe", hero.skill("sinn").unwrap().0);
+	/// assert_eq!("schleichen", hero.skill("schl").unwrap().0);
+	/// assert!(hero.skill("sch").unwrap_err().to_string().contains("schwimmen"));
+	/// assert!(hero.skill("schleichn").unwrap_err().to_string().contains("did you mean 'schleichen'"));
+	/// ```
+	pub fn skill(&self, name: &str) -> Result<(&str,&(isize,[Quality;3]))>
+	{
+		lookup(&self.skills, name)
+	}
+
+	/// Looks up a combat talent by name, returning its canonical name along with its attack and parry value.
+	///
+	/// The lookup works like the one of *Hero::skill*.
+	pub fn combat_talent(&self, name: &str) -> Result<(&str,&(isize,Option<isize>))>
+	{
+		lookup(&self.combat, name)
+	}
+
+	/// Picks a hero by name from the names of a party, case insensitive, any unique prefix will do.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::Hero;
+	/// # use dsa::error::ErrorKind;
+	/// let names = ["Alrik Fuchs", "Alrike", "Gerda"];
+	/// assert_eq!(2, Hero::select(&names, "gerda").unwrap());
+	/// assert_eq!(1, Hero::select(&names, "Alrike").unwrap());
+	/// assert!(Hero::select(&names, "alri").is_err());
+	/// assert!(matches!(Hero::select(&names, "hugo").unwrap_err().kind(), ErrorKind::UnknownHero(..)));
+	/// ```
+	pub fn select<S: AsRef<str>>(names: &[S], name: &str) -> Result<usize>
+	{
+		let name = name.to_lowercase();
+		let lowercase: Vec<String> = names.iter().map(|name| name.as_ref().to_lowercase()).collect();
+		let matching: Vec<usize> = match lowercase.iter().position(|candidate| *candidate == name)
+		{
+			Some(index) => vec![index],
+			None => (0..names.len()).filter(|index| lowercase[*index].starts_with(&name)).collect(),
+		};
+		match matching[..]
+		{
+			[index] => Ok(index),
+			[] => bail!(ErrorKind::UnknownHero(name, names.iter().map(|name| name.as_ref().to_string()).collect())),
+			_ => bail!(ErrorKind::AmbiguousHero(name, matching.iter().map(|index| names[*index].as_ref().to_string()).collect())),
+		}
+	}
+
+	/// Whether the hero has a special ability matching the name, see *SpecialAbility::matches*.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::{Hero, SpecialAbility};
+	/// let mut hero = Hero::default();
+	/// hero.special_abilities.push(SpecialAbility
+	/// {
+	///     name: "Merkmalskenntnis".to_string(),
+	///     parameter: Some("Elementar".to_string()),
+	/// });
+	/// assert!(hero.has_special_ability("merkmalskenntnis"));
+	/// assert!(hero.has_special_ability("Merkmalskenntnis: Elementar"));
+	/// assert!(!hero.has_special_ability("Merkmalskenntnis: Dämonisch"));
+	/// assert!(!hero.has_special_ability("Kampfreflexe"));
+	/// ```
+	pub fn has_special_ability(&self, name: &str) -> bool
+	{
+		self.special_abilities(name).next().is_some()
+	}
+
+	/// Total protection (RS) of all worn armor.
+	pub fn protection(&self) -> isize
+	{
+		self.armor.iter().map(|armor| armor.protection).sum()
+	}
+
+	/// Total encumbrance (BE) of all worn armor.
+	pub fn encumbrance(&self) -> isize
+	{
+		self.armor.iter().map(|armor| armor.encumbrance).sum()
+	}
+
+	/// Effective encumbrance on a skill, based on the total encumbrance of the worn armor.
+	///
+	/// Skills without encumbrance information are not encumbered.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::{Hero, Armor, Encumbrance};
+	/// let mut hero = Hero::default();
+	/// hero.armor.push(Armor
+	/// {
+	///     name: "Kettenhemd".to_string(),
+	///     protection: 4,
+	///     encumbrance: 3,
+	/// });
+	/// hero.skill_encumbrance.insert("schleichen".to_string(),"BEx2".parse().unwrap());
+	/// hero.skill_encumbrance.insert("klettern".to_string(),Encumbrance::Offset(-2));
+	/// assert_eq!(6, hero.skill_encumbrance("Schleichen"));
+	/// assert_eq!(1, hero.skill_encumbrance("klettern"));
+	/// assert_eq!(0, hero.skill_encumbrance("sinnenschärfe"));
+	/// ```
+	pub fn skill_encumbrance(&self, skill: &str) -> isize
+	{
+		self.skill_encumbrance.get(&skill.to_lowercase())
+			.map(|encumbrance| encumbrance.effective(self.encumbrance()))
+			.unwrap_or(0)
+	}
+
+	/// Case insensitive lookup of a melee or ranged weapon by name.
+	pub fn weapon(&self, name: &str) -> Option<&Weapon>
+	{
+		let name = name.to_lowercase();
+		self.weapons.iter()
+			.chain(&self.ranged_weapons)
+			.find(|weapon| weapon.name.to_lowercase() == name)
+	}
+}
+
+/// The path of an element like `helden/held[@name='Alrik']/eigenschaften`, for errors.
+fn path(node: roxmltree::Node) -> String
+{
+	let mut parts: Vec<String> = node.ancestors()
+		.filter(|node| node.is_element())
+		.map(|node| match node.attribute("name")
+		{
+			Some(name) => format!("{}[@name='{}']", node.tag_name().name(), name),
+			None => node.tag_name().name().to_string(),
+		})
+		.collect();
+	parts.reverse();
+	parts.join("/")
+}
+
+/// Lower case with umlauts spelled out, so "Sinnenschärfe" and "sinnenschaerfe" are the same.
+fn normalize(name: &str) -> String
+{
+	name.trim()
+		.to_lowercase()
+		.replace('ä', "ae")
+		.replace('ö', "oe")
+		.replace('ü', "ue")
+		.replace('ß', "ss")
+}
+
+/// Levenshtein distance of two strings.
+fn distance(left: &str, right: &str) -> usize
+{
+	let right: Vec<char> = right.chars().collect();
+	let mut row: Vec<usize> = (0..=right.len()).collect();
+	for (i, left) in left.chars().enumerate()
+	{
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+		for (j, right) in right.iter().enumerate()
+		{
+			let substitution = diagonal + if left == *right { 0 } else { 1 };
+			diagonal = row[j + 1];
+			row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+		}
+	}
+	row[right.len()]
+}
+
+/// Looks up a name in a map with lower case keys, accepting different spellings of umlauts and unique prefixes.
+fn lookup<'a, V>(map: &'a HashMap<String,V>, name: &str) -> Result<(&'a str,&'a V)>
+{
+	if let Some((key,value)) = map.get_key_value(&name.to_lowercase())
+	{
+		return Ok((key,value));
+	}
+
+	let wanted = normalize(name);
+	let normalized: Vec<(String,&'a String,&'a V)> = map.iter()
+		.map(|(key,value)| (normalize(key),key,value))
+		.collect();
+	if let Some((_,key,value)) = normalized.iter().find(|(normalized,_,_)| *normalized == wanted)
+	{
+		return Ok((key,value));
+	}
+
+	let prefixed: Vec<_> = normalized.iter()
+		.filter(|(normalized,_,_)| !wanted.is_empty() && normalized.starts_with(&wanted))
+		.collect();
+	match prefixed.len()
+	{
+		1 => return Ok((prefixed[0].1,prefixed[0].2)),
+		0 => {},
+		_ =>
+		{
+			let mut candidates: Vec<_> = prefixed.iter().map(|(_,key,_)| key.to_string()).collect();
+			candidates.sort();
+			bail!(ErrorKind::AmbiguousSkill(name.to_string(), candidates));
+		},
+	}
+
+	let mut similar: Vec<_> = normalized.iter()
+		.map(|(normalized,key,_)| (distance(normalized, &wanted),*key))
+		.filter(|(distance,_)| *distance <= 2.max(wanted.chars().count() / 3))
+		.collect();
+	similar.sort();
+	bail!(ErrorKind::UnknownSkill(name.to_string(), similar.iter().take(3).map(|(_,key)| key.to_string()).collect()));
+}
+
+/// A melee or ranged weapon of a *Hero*.
+///
+/// Weapons are read from the `gegenstände` element, every `gegenstand` with a `Nahkampfwaffe` or `Fernkampfwaffe` child is a weapon.
+///
+/// # Examples
+///
+/// ```
+/// use dsa::Hero;
+///
+/// let hero = r#"<helden><held name="Alrik">
+///     <eigenschaften/>
+///     <gegenstände>
+///         <gegenstand name="Säbel" anzahl="1">
+///             <Nahkampfwaffe>
+///                 <Trefferpunkte mul="1" w="6" sum="3"/>
+///                 <TrefferpunkteKK kk="12" schrittweite="4"/>
+///                 <Initiative init="0"/>
+///                 <Waffenmodif at="0" pa="-1"/>
+///             </Nahkampfwaffe>
+///         </gegenstand>
+///         <gegenstand name="Kurzbogen" anzahl="1">
+///             <Fernkampfwaffe>
+///                 <Trefferpunkte mul="1" w="6" sum="4"/>
+///             </Fernkampfwaffe>
+///         </gegenstand>
+///     </gegenstände>
+/// </held></helden>"#.parse::<Hero>().unwrap();
+///
+/// let sabre = hero.weapon("säbel").unwrap();
+/// assert_eq!("1W6+3", sabre.damage.to_string());
+/// assert_eq!(Some((12,4)), sabre.strength);
+/// assert_eq!((0,-1), sabre.modifier);
+/// assert_eq!(1, sabre.strength_bonus(16));
+/// assert_eq!(-1, sabre.strength_bonus(8));
+/// assert_eq!(1, hero.ranged_weapons.len());
+/// ```
+#[derive(Debug,Clone,PartialEq,Eq,Default)]
+pub struct Weapon
+{
+	/// Name as exported, e.g. Säbel
+	pub name: String,
+	/// Hit points (TP) dealt, e.g. 1W6+4
+	pub damage: dice::Expression,
+	/// TP/KK as threshold and step size, if any
+	pub strength: Option<(isize,isize)>,
+	/// Initiative modifier
+	pub initiative: isize,
+	/// Weapon modifier (WM) for attack and parry
+	pub modifier: (isize,isize),
+}
+
+impl Weapon
+{
+	/// Additional TP caused by the strength (KK) of the wielder as per TP/KK.
+	pub fn strength_bonus(&self, strength: isize) -> isize
+	{
+		match self.strength
+		{
+			Some((threshold,step)) if step > 0 => (strength - threshold) / step,
+			_ => 0,
+		}
+	}
+
+	fn from_node(name: &str, elem: roxmltree::Node) -> Self
+	{
+		let child = |tag: &str| elem.children().find(|child| child.has_tag_name(tag));
+		let value = |tag: &str, attribute: &str| child(tag)
+			.and_then(|child| child.attribute(attribute))
+			.and_then(|value| value.trim().parse::<isize>().ok());
+
+		Weapon
+		{
+			name: name.to_string(),
+			damage: dice::Expression
+			{
+				count: value("Trefferpunkte","mul").unwrap_or(1),
+				sides: value("Trefferpunkte","w").unwrap_or(6),
+				modifier: value("Trefferpunkte","sum").unwrap_or(0),
+			},
+			strength: value("TrefferpunkteKK","kk").zip(value("TrefferpunkteKK","schrittweite")),
+			initiative: value("Initiative","init").unwrap_or(0),
+			modifier: (value("Waffenmodif","at").unwrap_or(0),value("Waffenmodif","pa").unwrap_or(0)),
+		}
+	}
+}
+
+/// A piece of armor worn by a *Hero*.
+///
+/// Armor is read from the `gegenstände` element, every `gegenstand` with a `Rüstung` child is a piece of armor.
+/// If the equipment (`ausrüstungen`) names any armor, only the armor of its first set is worn.
+/// Otherwise all armor is assumed to be worn, unless its `anzahl` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use dsa::Hero;
+///
+/// let hero = r#"<helden><held name="Alrik">
+///     <eigenschaften/>
+///     <gegenstände>
+///         <gegenstand name="Kettenhemd" anzahl="1">
+///             <Rüstung rs="4" be="3"/>
+///         </gegenstand>
+///         <gegenstand name="Lederhelm" anzahl="1">
+///             <Rüstung rs="1" be="1"/>
+///         </gegenstand>
+///     </gegenstände>
+/// </held></helden>"#.parse::<Hero>().unwrap();
+///
+/// assert_eq!(2, hero.armor.len());
+/// assert_eq!(5, hero.protection());
+/// assert_eq!(4, hero.encumbrance());
+///
+/// let hero = r#"<helden><held name="Alrik">
+///     <eigenschaften/>
+///     <gegenstände>
+///         <gegenstand name="Kettenhemd" anzahl="1">
+///             <Rüstung rs="4" be="3"/>
+///         </gegenstand>
+///         <gegenstand name="Lederhelm" anzahl="1">
+///             <Rüstung rs="1" be="1"/>
+///         </gegenstand>
+///         <gegenstand name="Gambeson" anzahl="0">
+///             <Rüstung rs="2" be="2"/>
+///         </gegenstand>
+///     </gegenstände>
+///     <ausrüstungen>
+///         <heldenausruestung name="ruestung1" nummer="1" ruestungsname="Lederhelm" set="0"/>
+///         <heldenausruestung name="ruestung1" nummer="1" ruestungsname="Kettenhemd" set="1"/>
+///     </ausrüstungen>
+/// </held></helden>"#.parse::<Hero>().unwrap();
+///
+/// assert_eq!(1, hero.armor.len());
+/// assert_eq!("Lederhelm", hero.armor[0].name);
+/// ```
+#[derive(Debug,Clone,PartialEq,Eq,Default)]
+pub struct Armor
+{
+	/// Name as exported, e.g. Kettenhemd
+	pub name: String,
+	/// Protection (RS)
+	pub protection: isize,
+	/// Encumbrance (BE)
+	pub encumbrance: isize,
+}
+
+impl Armor
+{
+	fn from_node(name: &str, elem: roxmltree::Node) -> Self
+	{
+		let value = |attribute: &str| elem.attribute(attribute)
+			.and_then(|value| value.trim().parse::<isize>().ok())
+			.unwrap_or(0);
+
+		Armor
+		{
+			name: name.to_string(),
+			protection: value("rs"),
+			encumbrance: value("be"),
+		}
+	}
+}
+
+/// The effect of encumbrance (BE) on a skill as written on the talent, e.g. "BEx2" or "BE-2".
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Encumbrance;
+/// assert_eq!(Encumbrance::None, "-".parse().unwrap());
+/// assert_eq!(Encumbrance::Multiple(1), "BE".parse().unwrap());
+/// assert_eq!(Encumbrance::Multiple(2), "BEx2".parse().unwrap());
+/// assert_eq!(Encumbrance::Offset(-2), "be-2".parse().unwrap());
+/// assert_eq!(6, Encumbrance::Multiple(2).effective(3));
+/// assert_eq!(0, Encumbrance::Offset(-4).effective(3));
+/// ```
+#[derive(Debug,PartialEq,Eq,Hash,Clone,Copy)]
+pub enum Encumbrance
+{
+	/// The skill is not affected at all
+	None,
+	/// The encumbrance is multiplied, e.g. BEx2
+	Multiple(isize),
+	/// The encumbrance is offset by a fixed value, e.g. BE-2
+	Offset(isize),
+}
+
+impl Encumbrance
+{
+	/// Effective encumbrance given the total encumbrance of the armor.
+	pub fn effective(&self, encumbrance: isize) -> isize
+	{
+		match self
+		{
+			Encumbrance::None => 0,
+			Encumbrance::Multiple(factor) => 0.max(encumbrance * factor),
+			Encumbrance::Offset(offset) => 0.max(encumbrance + offset),
+		}
+	}
+}
+
+impl std::str::FromStr for Encumbrance
 {
 	type Err = Error;
 
 	fn from_str(input: &str) -> Result<Self>
+	{
+		let input = input.trim().to_lowercase();
+		if input.is_empty() || input == "-"
+		{
+			return Ok(Encumbrance::None);
+		}
+		let rest = input.strip_prefix("be").ok_or_else(|| format!("malformed encumbrance '{}'", input))?;
+		match rest.chars().next()
+		{
+			None => Ok(Encumbrance::Multiple(1)),
+			Some('x') | Some('*') => Ok(Encumbrance::Multiple(rest[1..].parse()?)),
+			Some('+') => Ok(Encumbrance::Offset(rest[1..].parse()?)),
+			Some('-') => Ok(Encumbrance::Offset(rest.parse()?)),
+			_ => bail!("malformed encumbrance '{}'", input),
+		}
+	}
+}
+
+impl std::fmt::Display for Encumbrance
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		match self
+		{
+			Encumbrance::None => write!(f, "-"),
+			Encumbrance::Multiple(1) => write!(f, "BE"),
+			Encumbrance::Multiple(factor) => write!(f, "BEx{}", factor),
+			Encumbrance::Offset(offset) => write!(f, "BE{:+}", offset),
+		}
+	}
+}
+
+/// An advantage or disadvantage (Vorteil/Nachteil) of a *Hero*.
+///
+/// The Heldensoftware does not distinguish between the two in its export, both are listed in the `vt` element.
+/// Numeric values (e.g. the 7 of "Jähzorn 7") end up in *value*, anything else (e.g. the "Orks" of "Vorurteile gegen Orks") in *parameter*.
+///
+/// # Examples
+///
+/// ```
+/// use dsa::Hero;
+///
+/// let hero = r#"<helden><held name="Alrik">
+///     <eigenschaften/>
+///     <vt>
+///         <vorteil name="Glück"/>
+///         <vorteil name="Jähzorn" value="7"/>
+///         <vorteil name="Vorurteile (gegen)">
+///             <auswahl position="0" value="6"/>
+///             <auswahl position="1" value="Orks"/>
+///         </vorteil>
+///     </vt>
+/// </held></helden>"#.parse::<Hero>().unwrap();
+///
+/// assert_eq!(3, hero.advantages.len());
+/// assert_eq!("Glück", hero.advantages[0].name);
+/// assert_eq!(Some(7), hero.advantages[1].value);
+/// assert_eq!(Some(6), hero.advantages[2].value);
+/// assert_eq!(Some("Orks"), hero.advantages[2].parameter.as_deref());
+/// ```
+#[derive(Debug,Clone,PartialEq,Eq,Default)]
+pub struct Advantage
+{
+	/// Name as exported, e.g. Jähzorn
+	pub name: String,
+	/// Numeric value, if any
+	pub value: Option<isize>,
+	/// Free-text parameter, if any
+	pub parameter: Option<String>,
+}
+
+/// A special ability (Sonderfertigkeit) of a *Hero*.
+///
+/// Abilities exported as "Name: Parameter" (e.g. "Merkmalskenntnis: Elementar") are split into *name* and *parameter*.
+/// The same goes for abilities carrying their parameter in child elements (e.g. the talent of a Talentspezialisierung).
+///
+/// # Examples
+///
+/// ```
+/// use dsa::Hero;
+///
+/// let hero = r#"<helden><held name="Alrik">
+///     <eigenschaften/>
+///     <sf>
+///         <sonderfertigkeit name="Kampfreflexe"/>
+///         <sonderfertigkeit name="Merkmalskenntnis: Elementar"/>
+///         <sonderfertigkeit name="Talentspezialisierung Schwerter">
+///             <talent name="Schwerter"/>
+///             <spezialisierung name="Langschwert"/>
+///         </sonderfertigkeit>
+///     </sf>
+/// </held></helden>"#.parse::<Hero>().unwrap();
+///
+/// assert_eq!(3, hero.special_abilities.len());
+/// assert!(hero.has_special_ability("kampfreflexe"));
+/// assert_eq!(Some("Elementar"), hero.special_abilities[1].parameter.as_deref());
+/// assert_eq!(Some("Schwerter, Langschwert"), hero.special_abilities[2].parameter.as_deref());
+/// ```
+#[derive(Debug,Clone,PartialEq,Eq,Default)]
+pub struct SpecialAbility
+{
+	/// Name as exported, e.g. Kampfreflexe
+	pub name: String,
+	/// Free-text parameter, if any
+	pub parameter: Option<String>,
+}
+
+impl SpecialAbility
+{
+	/// Case insensitive match against either the plain name or "Name: Parameter".
+	pub fn matches(&self, query: &str) -> bool
+	{
+		let query = query.trim().to_lowercase();
+		if self.name.to_lowercase() == query
+		{
+			return true;
+		}
+		match (&self.parameter, query.split_once(':'))
+		{
+			(Some(parameter), Some((name, value))) =>
+				self.name.to_lowercase() == name.trim() && parameter.to_lowercase() == value.trim(),
+			_ => false,
+		}
+	}
+
+	fn from_node(elem: roxmltree::Node) -> Self
+	{
+		let name = elem.attribute("name").unwrap_or("");
+		let (name, parameter) = match name.split_once(':')
+		{
+			Some((name, parameter)) => (name.trim(), Some(parameter.trim().to_string())),
+			None => (name.trim(), None),
+		};
+		let children = elem.children()
+			.filter(|child| child.is_element())
+			.filter_map(|child| child.attribute("name").or_else(|| child.attribute("value")))
+			.collect::<Vec<_>>();
+		let parameter = match (parameter, children.is_empty())
+		{
+			(parameter, true) => parameter,
+			(None, false) => Some(children.join(", ")),
+			(Some(parameter), false) => Some(format!("{}, {}", parameter, children.join(", "))),
+		};
+		SpecialAbility
+		{
+			name: name.to_string(),
+			parameter,
+		}
+	}
+}
+
+impl Advantage
+{
+	fn from_node(elem: roxmltree::Node) -> Self
+	{
+		let mut advantage = Advantage
+		{
+			name: elem.attribute("name").unwrap_or("").to_string(),
+			value: None,
+			parameter: None,
+		};
+		let values = elem.attribute("value").into_iter()
+			.chain(elem.children()
+				.filter(|child| child.has_tag_name("auswahl"))
+				.filter_map(|child| child.attribute("value"))
+			);
+		for value in values.map(str::trim).filter(|value| !value.is_empty())
+		{
+			match value.parse::<isize>()
+			{
+				Ok(value) => advantage.value = Some(value),
+				Err(_) =>
+				{
+					advantage.parameter = Some(match advantage.parameter.take()
+					{
+						Some(parameter) => format!("{}, {}", parameter, value),
+						None => value.to_string(),
+					});
+				},
+			}
+		}
+		advantage
+	}
+}
+
+impl Hero
+{
+	/// Parses every hero of an export, which may contain several `held` elements.
+	///
+	/// Every hero is parsed on its own, so a broken one does not keep the others from being loaded.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::Hero;
+	/// let heroes = Hero::parse_all(r#"<helden>
+	///     <held name="Alrik"><eigenschaften/></held>
+	///     <held name="Gerda"><eigenschaften/></held>
+	///     <held name="Hugo"/>
+	/// </helden>"#).unwrap();
+	/// assert_eq!("Alrik", heroes[0].as_ref().unwrap().name);
+	/// assert_eq!("Gerda", heroes[1].as_ref().unwrap().name);
+	/// assert!(heroes[2].is_err());
+	///
+	/// // parsing a single hero takes the first one
+	/// assert_eq!("Alrik", r#"<helden><held name="Alrik"><eigenschaften/></held><held name="Gerda"><eigenschaften/></held></helden>"#.parse::<Hero>().unwrap().name);
+	/// ```
+	pub fn parse_all(input: &str) -> Result<Vec<Result<Self>>>
 	{
 		let document = roxmltree::Document::parse(input).chain_err(|| "xml document could not be parsed")?;
 
-		// get the hero
 		let root = document.root_element();
 		if ! root.has_tag_name("helden")
 		{
-			bail!("unknown root element");
+			bail!(ErrorKind::MissingElement("helden".to_string()));
+		}
+		let heroes = root.children()
+			.filter(|child| child.has_tag_name("held"))
+			.map(|held| Hero::from_node(held).chain_err(|| format!("failed parsing hero '{}'", held.attribute("name").unwrap_or_default())))
+			.collect::<Vec<_>>();
+		if heroes.is_empty()
+		{
+			bail!(ErrorKind::MissingElement("helden/held".to_string()));
 		}
-		let held = root.children()
-			.find(|child| child.has_tag_name("held"))
-			.ok_or("root element does not contain held element")?;
 
+		Ok(heroes)
+	}
+
+	/// Parses a single `held` element.
+	fn from_node(held: roxmltree::Node) -> Result<Self>
+	{
 		// get the qualities
 		let qualities = held.children()
 			.find(|elem| elem.has_tag_name("eigenschaften"))
-			.ok_or("hero does not have base values")?;
+			.ok_or_else(|| ErrorKind::MissingElement(format!("{}/eigenschaften", path(held))))?;
 		let mut qualities: HashMap<_,isize> = qualities.children()
+			.filter(|elem| elem.is_element())
 			.map(|elem|
 				{
 					let name = elem.attribute("name").unwrap_or("").to_lowercase();
@@ -69,39 +736,147 @@ impl std::str::FromStr for Hero
 		let health_base = qualities.remove("lebensenergie").unwrap_or(0);
 		let stamina_base = qualities.remove("ausdauer").unwrap_or(0);
 		let astral_base = qualities.remove("astralenergie").unwrap_or(0);
+		// karma is not supported yet, but it is not a problem of the export either
+		qualities.remove("karmaenergie");
+		let mut warnings = vec![];
+		let mut qualities: Vec<_> = qualities.into_iter().collect();
+		qualities.sort();
 		let qualities: HashMap<_,_> = qualities.into_iter()
 			.filter_map(|(k,v)|
 			{
 				k.parse::<Quality>()
 					.map(|k| (k,v))
+					.map_err(|error| warnings.push(format!("quality skipped: {}", error)))
 					.ok()
 			})
 			.collect();
 
 		use Quality::*;
 
-		let skills: HashMap<_,_> = held.children()
+		let mut skills = HashMap::new();
+		for elem in held.children()
 			.filter(|elem| elem.has_tag_name("talentliste") || elem.has_tag_name("zauberliste"))
 			.flat_map(|elem| elem.children())
-			.map(|elem| -> Result<(String,(isize,[Quality;3]))>
+			.filter(|elem| elem.is_element())
+		{
+			let name = elem.attribute("name").unwrap_or("").to_lowercase();
+			let skill = || -> Result<(isize,[Quality;3])>
+			{
+				let value = elem.attribute("value").and_then(|i| i.parse().ok()).unwrap_or(0);
+				let raw = elem.attribute("probe").ok_or_else(|| ErrorKind::MissingElement(format!("{}/@probe", path(elem))))?;
+				let probe = raw.trim().trim_start_matches('(').trim_end_matches(')');
+				match probe.split('/').map(|quality| quality.parse::<Quality>()).collect::<Result<Vec<_>>>()
 				{
-					let name = elem.attribute("name").unwrap_or("").to_lowercase();
-					let value = elem.attribute("value").and_then(|i| i.parse().ok()).unwrap_or(0);
-					let probe = elem.attribute("probe").ok_or("probe not parsable")?;
-					let probe = probe.trim().trim_start_matches('(').trim_end_matches(')');
-					let probe = probe.split('/').map(|quality| quality.parse::<Quality>()).collect::<Result<Vec<_>>>()?;
-					if probe.len() != 3
+					Ok(probe) if probe.len() == 3 => Ok((value,[probe[0],probe[1],probe[2]])),
+					_ => bail!(ErrorKind::MalformedProbe(name.clone(), raw.to_string())),
+				}
+			};
+			match skill()
+			{
+				Ok((value,probe)) =>
+				{
+					// the skill can be parsed, but rolling for it would fail
+					for quality in probe.iter().filter(|quality| !qualities.contains_key(quality)).collect::<BTreeSet<_>>()
 					{
-						bail!("skill does not have three 'probe'");
+						warnings.push(format!("skill '{}' rolls on {:?} which the hero lacks", name, quality));
 					}
-					Ok((name,(value,[probe[0],probe[1],probe[2]])))
+					skills.insert(name,(value,probe));
+				},
+				Err(error) => warnings.push(format!("skill '{}' skipped: {}", name, error)),
+			}
+		}
+		let skill_encumbrance: HashMap<_,_> = held.children()
+			.filter(|elem| elem.has_tag_name("talentliste"))
+			.flat_map(|elem| elem.children())
+			.filter_map(|elem|
+				{
+					let name = elem.attribute("name")?.to_lowercase();
+					let encumbrance = elem.attribute("be")?.parse::<Encumbrance>().ok()?;
+					Some((name,encumbrance))
+				})
+			.collect();
+
+		let combat: HashMap<_,_> = held.children()
+			.filter(|elem| elem.has_tag_name("kampf"))
+			.flat_map(|elem| elem.children())
+			.filter(|elem| elem.has_tag_name("kampfwerte"))
+			.filter_map(|elem|
+				{
+					let value = |tag: &str| elem.children()
+						.find(|child| child.has_tag_name(tag))
+						.and_then(|child| child.attribute("value"))
+						.and_then(|value| value.trim().parse::<isize>().ok());
+					let name = elem.attribute("name")?.to_lowercase();
+					Some((name,(value("attacke")?,value("parade"))))
 				})
-			.filter_map(Result::ok)
 			.collect();
 
+		let advantages = held.children()
+			.filter(|elem| elem.has_tag_name("vt"))
+			.flat_map(|elem| elem.children())
+			.filter(|elem| elem.has_tag_name("vorteil"))
+			.map(Advantage::from_node)
+			.collect();
+
+		let special_abilities = held.children()
+			.filter(|elem| elem.has_tag_name("sf"))
+			.flat_map(|elem| elem.children())
+			.filter(|elem| elem.has_tag_name("sonderfertigkeit"))
+			.map(SpecialAbility::from_node)
+			.collect();
+
+		let (mut weapons, mut ranged_weapons, mut armor) = (vec![], vec![], vec![]);
+		for item in held.children()
+			.filter(|elem| elem.has_tag_name("gegenstände"))
+			.flat_map(|elem| elem.children())
+			.filter(|elem| elem.has_tag_name("gegenstand"))
+			// items which ran out are still listed
+			.filter(|elem| elem.attribute("anzahl").map(|count| count.trim() != "0").unwrap_or(true))
+		{
+			let name = item.attribute("name").unwrap_or("");
+			for elem in item.children()
+			{
+				if elem.has_tag_name("Nahkampfwaffe")
+				{
+					weapons.push(Weapon::from_node(name, elem));
+				}
+				if elem.has_tag_name("Fernkampfwaffe")
+				{
+					ranged_weapons.push(Weapon::from_node(name, elem));
+				}
+				if elem.has_tag_name("Rüstung")
+				{
+					armor.push(Armor::from_node(name, elem));
+				}
+			}
+		}
+
+		// only the armor of the first set of the equipment is worn, everything else is merely carried
+		let worn: Vec<_> = held.children()
+			.filter(|elem| elem.has_tag_name("ausrüstungen"))
+			.flat_map(|elem| elem.children())
+			.filter(|elem| elem.has_tag_name("heldenausruestung"))
+			.filter(|elem| elem.attribute("set").unwrap_or("0") == "0")
+			.filter_map(|elem| elem.attribute("ruestungsname"))
+			.collect();
+		if !worn.is_empty()
+		{
+			armor = worn.into_iter()
+				.filter_map(|name| match armor.iter().find(|armor| armor.name == name)
+				{
+					Some(armor) => Some(armor.clone()),
+					None =>
+					{
+						warnings.push(format!("worn armor '{}' skipped: no such item", name));
+						None
+					},
+				})
+				.collect();
+		}
+
 		let hero = Hero
 		{
-			name: held.attribute("name").ok_or("hero does not have a name")?.into(),
+			name: held.attribute("name").ok_or_else(|| ErrorKind::MissingElement(format!("{}/@name", path(held))))?.into(),
 			health:
 			{
 				let ko = *qualities.get(&Constitution).unwrap_or(&0);
@@ -124,12 +899,43 @@ impl std::str::FromStr for Hero
 			},
 			qualities,
 			skills,
+			skill_encumbrance,
+			combat,
+			advantages,
+			special_abilities,
+			weapons,
+			ranged_weapons,
+			armor,
+			warnings,
 		};
 
 		Ok(hero)
 	}
 }
 
+impl std::str::FromStr for Hero
+{
+	type Err = Error;
+
+	/// Parses the first hero of the export, see *Hero::parse_all* for exports containing several heroes.
+	fn from_str(input: &str) -> Result<Self>
+	{
+		let document = roxmltree::Document::parse(input).chain_err(|| "xml document could not be parsed")?;
+
+		// get the hero
+		let root = document.root_element();
+		if ! root.has_tag_name("helden")
+		{
+			bail!(ErrorKind::MissingElement("helden".to_string()));
+		}
+		let held = root.children()
+			.find(|child| child.has_tag_name("held"))
+			.ok_or_else(|| ErrorKind::MissingElement("helden/held".to_string()))?;
+
+		Hero::from_node(held)
+	}
+}
+
 /// Base values of a Hero.
 ///
 /// The type implements *std::str::FromStr* and individual items are annotated with the possible values they are parsed from.
@@ -201,8 +1007,7 @@ impl std::str::FromStr for Quality
 			"at" | "attacke" | "nahkampf" | "combat" | "closecombat" => Ok(CloseCombat),
 			"pa" | "parrieren" | "parry" => Ok(Parry),
 			"fk" | "fernkampf" | "ranged" | "rangedcombat" => Ok(RangedCombat),
-			// TODO: make own error type with values
-			_ => bail!("unknown quality '{}'", input),
+			_ => bail!(ErrorKind::UnknownQuality(input.to_string())),
 		}
 	}
 }
@@ -7,13 +7,16 @@
 // -Werror in test mode
 #![cfg_attr(test, deny(warnings))]
 
+pub mod config;
+pub mod dice;
 pub mod error;
 pub mod output;
+pub mod session;
 mod action;
 mod hero;
 
 pub use action::{commands, Action};
-pub use hero::{Quality, Hero};
+pub use hero::{Quality, Hero, Advantage, SpecialAbility, Weapon, Armor, Encumbrance};
 
 /// Helper to create the basic app with options common between the cli-subcommand and the main app.
 ///
@@ -24,6 +27,26 @@ pub use hero::{Quality, Hero};
 /// let matches = app().try_get_matches_from(&["dsa-cli","-o","json","-V"]);
 /// assert_eq!(matches.err().unwrap().kind(),clap::ErrorKind::DisplayVersion);
 /// ```
+///
+/// A seed makes all dice deterministic, so rolls can be replayed:
+///
+/// ```
+/// # use dsa::{app, dice, Hero};
+/// # use dsa::commands::Dice;
+/// # use dsa::output::Output;
+/// let matches = app().try_get_matches_from(&["dsa-cli","--seed","42"]).unwrap();
+/// let source = dice::Source::seeded(matches.value_of_t("seed").unwrap());
+/// let mut dice = Dice::with_source(source);
+/// let matches = dice.usage().get_matches_from(&["dice","3W20"]);
+/// let output = dice.call(&Hero::default(),&matches).unwrap();
+///
+/// if let Output::Dice { dice, .. } = &output[0] {
+///     assert_eq!(&vec![14,20,9], dice);
+/// }
+/// # else {
+/// #     panic!("unexpected output");
+/// # }
+/// ```
 pub fn app() -> clap::Command<'static>
 {
 	use clap::ArgEnum;
@@ -43,6 +66,15 @@ pub fn app() -> clap::Command<'static>
 			.possible_values(output::Format::value_variants().iter().filter_map(ArgEnum::to_possible_value))
 			.default_value("human-readable")
 			.ignore_case(true)
+			.global(true)
+			)
+		.arg
+			( clap::Arg::new("seed")
+			.long("seed")
+			.value_name("SEED")
+			.help("seed for the dice to replay a session")
+			.takes_value(true)
+			.validator(|seed| seed.parse::<u64>())
 			)
 }
 
@@ -1,64 +1,317 @@
 use dsa::error::*;
 use dsa::output;
+use dsa::dice;
 use dsa::commands;
+use dsa::commands::Failure;
 use dsa::Action;
 use dsa::Hero;
+use dsa::Quality;
+use dsa::config::Config;
+use dsa::session::Session;
 
+use std::cell::RefCell;
 use std::fs::File;
 use std::io::Read;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use clap::Arg;
+use clap::{Arg, ArgMatches, Command};
 use clap::ArgEnum;
+use error_chain::bail;
 
-fn main() -> Result<()>
+/// Parses all heroes of a hero file, each of which may have failed on its own.
+fn load(path: &Path) -> Result<Vec<Result<Hero>>>
 {
-	let subcommands = vec!
-		[ Box::new(commands::Dump) as Box<dyn Action>
-		, Box::new(commands::Cli) as Box<dyn Action>
-		, Box::new(commands::Roll) as Box<dyn Action>
-		];
-	let mut subcommands: HashMap<String,Box<dyn Action>> = subcommands.into_iter()
-		.map(|command|
+	let mut text = String::new();
+	File::open(path)?.read_to_string(&mut text)?;
+	Hero::parse_all(&text)
+}
+
+/// Parses all hero files given, directories are searched for XML files.
+///
+/// Files and heroes which failed to load are returned separately along with their file, so they do not keep the others from being used.
+fn load_all<'a, I: IntoIterator<Item=&'a str>>(paths: I) -> Result<(Vec<Hero>,Vec<Failure>)>
+{
+	let mut files = vec![];
+	for path in paths.into_iter().map(PathBuf::from)
+	{
+		if path.is_dir()
 		{
-			(command.usage().get_name().to_owned(),command)
-		})
-		.collect();
+			let mut entries = std::fs::read_dir(&path)
+				.chain_err(|| format!("loading hero directory '{}'", path.display()))?
+				.map(|entry| Ok(entry?.path()))
+				.collect::<Result<Vec<_>>>()?;
+			entries.retain(|entry| entry.is_file() && entry.extension().map(|extension| extension.eq_ignore_ascii_case("xml")).unwrap_or(false));
+			entries.sort();
+			files.extend(entries);
+		}
+		else
+		{
+			files.push(path);
+		}
+	}
+	let failure = |path: &Path, error: Error| Failure
+	{
+		path: path.to_path_buf(),
+		error: error.chain_err(|| format!("failed loading hero file '{}'", path.display())),
+	};
+	let (mut heroes, mut failures) = (vec![], vec![]);
+	for path in files
+	{
+		match load(&path)
+		{
+			Ok(loaded) =>
+			{
+				for hero in loaded
+				{
+					match hero
+					{
+						Ok(hero) => heroes.push(hero),
+						Err(error) => failures.push(failure(&path, error)),
+					}
+				}
+			},
+			Err(error) => failures.push(failure(&path, error)),
+		}
+	}
+	Ok((heroes,failures))
+}
 
-	let matches = dsa::app()
+/// Keeps the heroes selected using `--hero`, if any.
+fn select(heroes: Vec<Hero>, matches: &ArgMatches) -> Result<Vec<Hero>>
+{
+	match matches.values_of("select")
+	{
+		Some(names) =>
+		{
+			let all: Vec<&str> = heroes.iter().map(|hero| hero.name.as_str()).collect();
+			names.map(|name| Ok(heroes[Hero::select(&all, name)?].clone())).collect()
+		},
+		None => Ok(heroes),
+	}
+}
+
+/// The session file given using `--session`, or the default one of the hero.
+fn session_path(matches: &ArgMatches, hero: &Hero) -> Option<PathBuf>
+{
+	matches.value_of("session").map(PathBuf::from).or_else(|| Session::default_path(hero))
+}
+
+/// Names of all gauges stored in the session of the hero, as far as the arguments can be made sense of.
+fn gauges(matches: &ArgMatches) -> Vec<String>
+{
+	let heroes = matches.values_of("hero")
+		.and_then(|paths| load_all(paths).ok())
+		.and_then(|(heroes,_)| select(heroes, matches).ok())
+		.unwrap_or_default();
+	let hero = match heroes[..]
+	{
+		[ref hero] => hero,
+		_ => return vec![],
+	};
+	session_path(matches, hero)
+		.filter(|path| path.exists())
+		.and_then(|path| Session::load(hero, path).ok())
+		.map(|session| session.gauges.into_keys().collect())
+		.unwrap_or_default()
+}
+
+/// The main app, with all options and subcommands.
+fn app(subcommands: &HashMap<String,Box<dyn Action>>) -> Command<'static>
+{
+	dsa::app()
 		.arg
 			( Arg::new("hero")
 			.short('f')
 			.long("file")
 			.value_name("FILE")
 			.env("DSACLI_FILE")
-			.help("the XML file for your hero")
+			.help("the XML file for your hero, repeat it or pass a directory to load a party for the cli")
 			.takes_value(true)
+			.multiple_occurrences(true)
+			.number_of_values(1)
 			.required(true)
 			)
+		.arg
+			( Arg::new("select")
+			.long("hero")
+			.value_name("NAME")
+			.env("DSACLI_HERO")
+			.help("the hero to use if the files contain several, repeat it to select a party for the cli")
+			.takes_value(true)
+			.multiple_occurrences(true)
+			.number_of_values(1)
+			)
+		.arg
+			( Arg::new("session")
+			.long("session")
+			.value_name("FILE")
+			.env("DSACLI_SESSION")
+			.help("the file to keep the tracker state in (default: per hero in the state directory)")
+			.takes_value(true)
+			.global(true)
+			)
 		.subcommands(subcommands.values().map(|command| command.usage()))
-		.get_matches();
+}
 
-	let hero =
+/// The state the actions share, filled once the config and the heroes are loaded.
+struct Shared
+{
+	session: Rc<RefCell<Session>>,
+	party: commands::Roster,
+	failures: Rc<RefCell<Vec<Failure>>>,
+	config: Rc<RefCell<Config>>,
+}
+
+/// Loads the heroes along with the session if the command is one of the *tracking* ones, then runs the command.
+fn run(matches: &ArgMatches, subcommands: &mut HashMap<String,Box<dyn Action>>, tracking: &[String], rng: &dice::Source, shared: &Shared, formatter: &dyn output::Formatter) -> Result<()>
+{
+	let Shared { session, party, failures, config } = shared;
+	let (command, args) = matches.subcommand().unwrap();
+	let (heroes, mut failed) = load_all(matches.values_of("hero").unwrap())?;
+	// reporting the files which failed to load is what validate is there for
+	if command != "validate"
+	{
+		// without any hero there is nothing left to do but to report why
+		if heroes.is_empty() && !failed.is_empty()
+		{
+			return Err(failed.remove(0).error);
+		}
+		for failure in failed.iter()
+		{
+			eprintln!("{}", formatter.format_error(&failure.error));
+		}
+	}
+	*failures.borrow_mut() = failed;
+	let heroes = select(heroes, matches)?;
+	party.set(heroes.clone());
+	let nobody = Hero::default();
+	let hero = match heroes.len()
 	{
-		let mut text = String::new();
-		let hero = matches.value_of("hero").unwrap();
-		let mut file = File::open(hero).chain_err(|| "loading hero file")?;
-		file.read_to_string(&mut text)?;
-		text.parse::<Hero>().chain_err(|| "failed parsing hero file")?
+		0 if command == "validate" && !failures.borrow().is_empty() => &nobody,
+		0 => bail!("no hero files found"),
+		1 => &heroes[0],
+		_ if command == "cli" || command == "party" || command == "validate" => &heroes[0],
+		_ => bail!("there are several heroes ({}), select one using --hero", heroes.iter().map(|hero| hero.name.as_str()).collect::<Vec<_>>().join(", ")),
 	};
 
-	let formatter: Box<dyn output::Formatter> = matches.value_of("format").map(|format| output::Format::from_str(format, true)).unwrap().unwrap().into();
+	if let Some(seed) = matches.value_of("seed")
+	{
+		rng.reseed(seed.parse()?);
+	}
+
+	// a broken session file must not keep anything else from working, the cli loads its own honouring --fresh
+	let path = Some(hero).filter(|_| !heroes.is_empty() && tracking.iter().any(|name| name == command)).and_then(|hero| session_path(matches, hero));
+	if let Some(ref path) = path
+	{
+		*session.borrow_mut() = if path.exists() { Session::load(hero, path)? } else { Session::new(hero) };
+		config.borrow().apply(&mut session.borrow_mut());
+	}
+	let before = session.borrow().clone();
 
-	let (command, args) = matches.subcommand().unwrap();
 	// we only add subcommands from that hashmap so it MUST be present
-	let command = subcommands.get_mut(command).unwrap_or_else(|| unreachable!());
-	for result in command.call(&hero, args)?.into_iter()
-		.map(|result| formatter.format(&result))
+	let outputs = subcommands.get_mut(command).unwrap_or_else(|| unreachable!()).call(hero, args)?;
+	for result in outputs.iter()
+		.map(|result| formatter.format(result))
 	{
 		println!("{}",result);
 	}
 
+	// the interactive cli persists its own changes, this only covers the trackers
+	let session = session.borrow();
+	if let Some(path) = path.filter(|_| *session != before)
+	{
+		session.save(path)?;
+	}
+
+	// validate is meant as a lint, so scripts can fail on it
+	let problems: usize = outputs.iter()
+		.map(|output| match output
+		{
+			output::Output::Validation { warnings, .. } => warnings.len(),
+			_ => 0,
+		})
+		.sum();
+	if problems > 0
+	{
+		bail!("{} problem{} found", problems, if problems == 1 { "" } else { "s" });
+	}
+
 	Ok(())
 }
 
+fn main() -> Result<()>
+{
+	let rng = dice::Source::default();
+	// loaded once the hero is known, trackers only ever access it during their call
+	let session = Rc::new(RefCell::new(Session::default()));
+	let party = commands::Roster::default();
+	let failures = Rc::new(RefCell::new(Vec::new()));
+	let config = Rc::new(RefCell::new(Config::default()));
+	let mut subcommands = vec!
+		[ Box::new(commands::Dump) as Box<dyn Action>
+		, commands::Cli::with_failures(rng.clone(),party.clone(),failures.clone())
+		, commands::Roll::with_source(rng.clone())
+		, commands::Odds::new_action()
+		, commands::Check::with_source(rng.clone())
+		, commands::Damage::with_source(rng.clone())
+		, commands::Dice::with_source(rng.clone())
+		, commands::Combat::with_source(Quality::CloseCombat,rng.clone())
+		, commands::Combat::with_source(Quality::Parry,rng.clone())
+		, commands::Log::with_session(session.clone())
+		, commands::Party::with_party(party.clone(),rng.clone())
+		, commands::Validate::with_failures(party.clone(),failures.clone())
+		];
+	let mut reserved = subcommands.iter().map(|command| command.usage().get_name().to_owned()).collect::<Vec<_>>();
+	reserved.extend(commands::RESERVED.iter().map(|name| name.to_string()));
+	subcommands.push(commands::Track::with_config(session.clone(),reserved.clone(),config.clone()));
+	subcommands.extend(vec!["health","astral","stamina"].into_iter()
+		.map(|name| commands::Tracker::with_session(name,session.clone())));
+	// the commands accessing the session
+	let mut tracking: Vec<String> = vec!["log","track","health","astral","stamina"].into_iter().map(str::to_string).collect();
+	let mut subcommands: HashMap<String,Box<dyn Action>> = subcommands.into_iter()
+		.map(|command|
+		{
+			(command.usage().get_name().to_owned(),command)
+		})
+		.collect();
+
+	// custom trackers are only known once the config and the session are loaded, so peek at the arguments first
+	let peek = app(&subcommands).ignore_errors(true).try_get_matches();
+	let formatter: Box<dyn output::Formatter> = peek.as_ref().ok()
+		.and_then(|matches| matches.value_of("format"))
+		.and_then(|format| output::Format::from_str(format, true).ok())
+		.unwrap_or(output::Format::HumanReadable)
+		.into();
+	*config.borrow_mut() = match Config::load_default()
+	{
+		Ok(config) => config,
+		Err(error) =>
+		{
+			eprintln!("{}", formatter.format_error(&error));
+			std::process::exit(1);
+		},
+	};
+	let trackers: Vec<String> = config.borrow().trackers.keys().cloned().collect();
+	for name in trackers.into_iter().chain(peek.iter().flat_map(gauges))
+	{
+		if !reserved.contains(&name) && !subcommands.contains_key(&name)
+		{
+			subcommands.insert(name.clone(),commands::Tracker::with_session(name.clone(),session.clone()));
+			tracking.push(name);
+		}
+	}
+
+	let matches = app(&subcommands).get_matches();
+
+	let formatter: Box<dyn output::Formatter> = matches.value_of("format").map(|format| output::Format::from_str(format, true)).unwrap().unwrap().into();
+
+	if let Err(error) = run(&matches, &mut subcommands, &tracking, &rng, &Shared { session, party, failures, config }, formatter.as_ref())
+	{
+		eprintln!("{}", formatter.format_error(&error));
+		std::process::exit(1);
+	}
+
+	Ok(())
+}
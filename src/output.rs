@@ -12,7 +12,10 @@
 //! assert_eq!("current health: 1/10 (10%)",output);
 //! ```
 
+use crate::dice;
+use crate::error::*;
 use crate::hero::*;
+use crate::session;
 
 use std::collections::HashMap;
 
@@ -58,6 +61,30 @@ pub trait Formatter
 {
 	/// Convert the *Output* to a *String* for further presentation to the user.
 	fn format(&self, data: &Output) -> String;
+
+	/// Convert an *Error*, including everything that caused it, to a *String* for presentation to the user.
+	///
+	/// By default, the messages of the error and its causes are joined by colons.
+	fn format_error(&self, error: &Error) -> String
+	{
+		error.iter().map(ToString::to_string).collect::<Vec<_>>().join(": ")
+	}
+}
+
+/// The error itself followed by everything that caused it.
+fn causes(error: &Error) -> impl Iterator<Item=&(dyn std::error::Error + 'static)>
+{
+	std::iter::successors(Some(error as &(dyn std::error::Error + 'static)), |error| error.source())
+}
+
+/// The most specific kind of an error, i.e. the first one in the chain which is not just a message.
+fn error_kind(error: &Error) -> &ErrorKind
+{
+	causes(error)
+		.filter_map(|error| error.downcast_ref::<Error>())
+		.map(Error::kind)
+		.find(|kind| !matches!(kind, ErrorKind::Msg(_)))
+		.unwrap_or_else(|| error.kind())
 }
 
 impl Formatter for formats::HumanReadable
@@ -67,15 +94,28 @@ impl Formatter for formats::HumanReadable
 		match data
 		{
 			Output::Dump(hero) => format!("{:#?}", hero),
-			Output::Roll {success,critical,remainder,checks,stat,dice,mods,mut base} =>
+			Output::Roll {hero,success,critical,remainder,checks,stat,dice,mods,encumbrance,mut base} =>
 			{
 				use std::io::Write;
 				use std::cmp::Ordering;
 
 				let mut output = vec![];
 
+				if let Some(hero) = hero
+				{
+					writeln!(output,"{}:", hero).unwrap();
+				}
+
+				let mods = mods + encumbrance;
 				let stat_mod = 0.max(mods - base);
-				writeln!(output,"base: {} (= {}, {:+} mod)", 0.max(base - mods), base, -mods).unwrap();
+				if *encumbrance != 0
+				{
+					writeln!(output,"base: {} (= {}, {:+} mod, {:+} BE)", 0.max(base - mods), base, encumbrance - mods, -encumbrance).unwrap();
+				}
+				else
+				{
+					writeln!(output,"base: {} (= {}, {:+} mod)", 0.max(base - mods), base, -mods).unwrap();
+				}
 				base = 0.max(base - mods);
 				if stat_mod > 0
 				{
@@ -104,7 +144,118 @@ impl Formatter for formats::HumanReadable
 				).unwrap();
 				String::from_utf8_lossy(&output).to_string()
 			},
+			Output::PartyRoll {skill,best,remainder,missing,failed} =>
+			{
+				let mut lines = vec![match remainder
+				{
+					Some(remainder) => format!("{}: best {} with {} TaP*", skill, best.join(", "), remainder),
+					None => format!("{}: nobody succeeded", skill),
+				}];
+				if !missing.is_empty()
+				{
+					lines.push(format!("lacking {}: {}", skill, missing.join(", ")));
+				}
+				for (hero,message) in failed
+				{
+					lines.push(format!("{} cannot roll: {}", hero, message));
+				}
+				lines.join("\n")
+			},
+			Output::Odds {skill,base,mods,encumbrance,success,critical_success,critical_failure} =>
+			{
+				format!("{}: {} (= {}, {:+} mod{})\n{:16} | {:6.2}%\n{:16} | {:6.2}%\n{:16} | {:6.2}%",
+					skill, 0.max(base - mods - encumbrance), base, -mods,
+					if *encumbrance != 0 { format!(", {:+} BE", -encumbrance) } else { String::new() },
+					"success", success * 100.0,
+					"critical success", critical_success * 100.0,
+					"critical failure", critical_failure * 100.0,
+				)
+			},
+			Output::OddsTable {skill,base,encumbrance,rows} =>
+			{
+				use std::io::Write;
+
+				let mut output = vec![];
+				writeln!(output,"{}: {}{}", skill, base,
+					if *encumbrance != 0 { format!(" ({:+} BE)", -encumbrance) } else { String::new() },
+				).unwrap();
+				write!(output,"{:>4} | {:>7} | {:>5}", "mod", "success", "TaP*").unwrap();
+				for (mods,success,remainder) in rows
+				{
+					write!(output,"\n{:+4} | {:6.2}% | {:5.2}", mods, success * 100.0, remainder).unwrap();
+				}
+				String::from_utf8_lossy(&output).to_string()
+			},
+			Output::Check {success,check,value,mods,die} =>
+			{
+				let target = value - mods;
+				let sym = match die
+				{
+					1 => "<<",
+					20 => ">>",
+					die if *die <= target => "<=",
+					_ => "> ",
+				};
+				format!("{:?}: {} (= {}, {:+} mod)\n{:13} | {:2} {} {:2}\n{}",
+					check, target, value, -mods,
+					"die", die, sym, target,
+					if *success { "success" } else { "failure" },
+				)
+			},
+			Output::Combat {success,critical,talent,check,value,mods,die,confirmation} =>
+			{
+				use std::io::Write;
+
+				let mut output = vec![];
+				let target = value - mods;
+				let sym = |die: &isize| match die
+				{
+					1 => "<<",
+					20 => ">>",
+					die if *die <= target => "<=",
+					_ => "> ",
+				};
+				writeln!(output,"{:?} with {}: {} (= {}, {:+} mod)", check, talent, target, value, -mods).unwrap();
+				writeln!(output,"{:13} | {:2} {} {:2}", "die", die, sym(die), target).unwrap();
+				if let Some(confirmation) = confirmation
+				{
+					writeln!(output,"{:13} | {:2} {} {:2}", "confirmation", confirmation, sym(confirmation), target).unwrap();
+				}
+				write!(output,"{}{}",
+					if *critical { "critical " } else { "" },
+					if *success { "success" } else { "failure" },
+				).unwrap();
+				String::from_utf8_lossy(&output).to_string()
+			},
+			Output::Damage {weapon,damage,dice,strength,bonus,total} =>
+			{
+				use std::io::Write;
+
+				let mut output = vec![];
+				writeln!(output,"{:13} | {}", weapon, damage).unwrap();
+				writeln!(output,"{:13} | {:?}", "dice", dice).unwrap();
+				writeln!(output,"{:13} | {:+}", "modifier", damage.modifier).unwrap();
+				writeln!(output,"{:13} | {:+}", "TP/KK", strength).unwrap();
+				writeln!(output,"{:13} | {:+}", "bonus", bonus).unwrap();
+				write!(output,"{} TP", total).unwrap();
+				String::from_utf8_lossy(&output).to_string()
+			},
+			Output::Dice {expression,dice,total} => format!("{}: {:?} {:+} = {}", expression, dice, expression.modifier, total),
+			Output::Log(entry) => format!("{} | {:8} | {:3}/{:<3} -> {:3}/{:<3} ({:+}){}",
+				humantime::format_rfc3339_seconds(entry.time),
+				entry.gauge,
+				entry.previous.current, entry.previous.max,
+				entry.current.current, entry.current.max,
+				entry.current.current - entry.previous.current,
+				entry.reason.as_ref().map(|reason| format!(" | {}", reason)).unwrap_or_default(),
+			),
 			Output::Gauge {name,current,max} => format!("current {}: {}/{} ({}%)",name,current,max,((100 * *current) as f64 / *max as f64).round()),
+			Output::Alias {name,expansion} => format!("alias {} = {}",name,expansion),
+			Output::Validation {hero,warnings} => match warnings.len()
+			{
+				0 => format!("{}: no problems found",hero),
+				_ => format!("{}: {} problem{} found\n{}",hero,warnings.len(),if warnings.len() == 1 { "" } else { "s" },warnings.iter().map(|warning| format!("  - {}",warning)).collect::<Vec<_>>().join("\n")),
+			},
 		}
 	}
 }
@@ -133,9 +284,56 @@ impl Formatter for formats::Json
 						"rolls" => rolls.iter().map(|roll| format!("{:?}",roll)).collect::<Vec<_>>(),
 					}))
 					.collect::<HashMap<_,_>>(),
+				"advantages" => hero.advantages.iter()
+					.map(|advantage| object!
+					{
+						"name" => advantage.name.to_string(),
+						"value" => advantage.value,
+						"parameter" => advantage.parameter.clone(),
+					})
+					.collect::<Vec<_>>(),
+				"special_abilities" => hero.special_abilities.iter()
+					.map(|ability| object!
+					{
+						"name" => ability.name.to_string(),
+						"parameter" => ability.parameter.clone(),
+					})
+					.collect::<Vec<_>>(),
+				"weapons" => hero.weapons.iter().map(|weapon| (weapon,false))
+					.chain(hero.ranged_weapons.iter().map(|weapon| (weapon,true)))
+					.map(|(weapon,ranged)| object!
+					{
+						"name" => weapon.name.to_string(),
+						"ranged" => ranged,
+						"damage" => weapon.damage.to_string(),
+						"strength" => weapon.strength.map(|(threshold,step)| vec![threshold,step]),
+						"initiative" => weapon.initiative,
+						"modifier" => vec![weapon.modifier.0,weapon.modifier.1],
+					})
+					.collect::<Vec<_>>(),
+				"armor" => hero.armor.iter()
+					.map(|armor| object!
+					{
+						"name" => armor.name.to_string(),
+						"protection" => armor.protection,
+						"encumbrance" => armor.encumbrance,
+					})
+					.collect::<Vec<_>>(),
+				"combat" => hero.combat.iter()
+					.map(|(name,(attack,parry))| (name,object!
+					{
+						"attack" => *attack,
+						"parry" => *parry,
+					}))
+					.collect::<HashMap<_,_>>(),
+				"skill_encumbrance" => hero.skill_encumbrance.iter()
+					.map(|(name,encumbrance)| (name,encumbrance.to_string()))
+					.collect::<HashMap<_,_>>(),
+				"warnings" => hero.warnings.clone(),
 			}.dump(),
-			Output::Roll {success,critical,remainder,checks,stat,dice,mods,base} => object!
+			Output::Roll {hero,success,critical,remainder,checks,stat,dice,mods,encumbrance,base} => object!
 			{
+				"hero" => hero.clone(),
 				"success" => *success,
 				"critical" => *critical,
 				"remainder" => *remainder,
@@ -143,16 +341,205 @@ impl Formatter for formats::Json
 				"stat" => &stat[..],
 				"dice" => &dice[..],
 				"mod" => *mods,
+				"encumbrance" => *encumbrance,
 				"base" => *base,
 			}.dump(),
+			Output::PartyRoll {skill,best,remainder,missing,failed} => object!
+			{
+				"skill" => skill.to_string(),
+				"best" => best.clone(),
+				"remainder" => *remainder,
+				"missing" => missing.clone(),
+				"failed" => failed.iter()
+					.map(|(hero,message)| object!
+					{
+						"hero" => hero.to_string(),
+						"message" => message.to_string(),
+					})
+					.collect::<Vec<_>>(),
+			}.dump(),
+			Output::Odds {skill,base,mods,encumbrance,success,critical_success,critical_failure} => object!
+			{
+				"skill" => skill.to_string(),
+				"base" => *base,
+				"mod" => *mods,
+				"encumbrance" => *encumbrance,
+				"success" => *success,
+				"critical_success" => *critical_success,
+				"critical_failure" => *critical_failure,
+			}.dump(),
+			Output::OddsTable {skill,base,encumbrance,rows} => ::json::JsonValue::from(rows.iter()
+				.map(|(mods,success,remainder)| object!
+				{
+					"skill" => skill.to_string(),
+					"base" => *base,
+					"mod" => *mods,
+					"encumbrance" => *encumbrance,
+					"success" => *success,
+					"remainder" => *remainder,
+				})
+				.collect::<Vec<_>>()).dump(),
+			Output::Check {success,check,value,mods,die} => object!
+			{
+				"success" => *success,
+				"check" => format!("{:?}",check),
+				"value" => *value,
+				"mod" => *mods,
+				"die" => *die,
+			}.dump(),
+			Output::Combat {success,critical,talent,check,value,mods,die,confirmation} => object!
+			{
+				"success" => *success,
+				"critical" => *critical,
+				"talent" => talent.to_string(),
+				"check" => format!("{:?}",check),
+				"value" => *value,
+				"mod" => *mods,
+				"die" => *die,
+				"confirmation" => *confirmation,
+			}.dump(),
+			Output::Damage {weapon,damage,dice,strength,bonus,total} => object!
+			{
+				"weapon" => weapon.to_string(),
+				"damage" => damage.to_string(),
+				"dice" => &dice[..],
+				"modifier" => damage.modifier,
+				"strength" => *strength,
+				"bonus" => *bonus,
+				"total" => *total,
+			}.dump(),
+			Output::Dice {expression,dice,total} => object!
+			{
+				"expression" => expression.to_string(),
+				"dice" => &dice[..],
+				"modifier" => expression.modifier,
+				"total" => *total,
+			}.dump(),
+			Output::Log(entry) => object!
+			{
+				"time" => humantime::format_rfc3339_seconds(entry.time).to_string(),
+				"gauge" => entry.gauge.to_string(),
+				"previous" => object!
+				{
+					"current" => entry.previous.current,
+					"max" => entry.previous.max,
+				},
+				"current" => object!
+				{
+					"current" => entry.current.current,
+					"max" => entry.current.max,
+				},
+				"reason" => entry.reason.clone(),
+			}.dump(),
 			Output::Gauge {name,current,max} => object!
 			{
 				"name" => name.to_string(),
 				"current" => *current,
 				"max" => *max,
 			}.dump(),
+			Output::Alias {name,expansion} => object!
+			{
+				"name" => name.to_string(),
+				"expansion" => expansion.to_string(),
+			}.dump(),
+			Output::Validation {hero,warnings} => object!
+			{
+				"hero" => hero.to_string(),
+				"warnings" => warnings.clone(),
+			}.dump(),
 		}
 	}
+
+	fn format_error(&self, error: &Error) -> String
+	{
+		use ::json::object;
+
+		let message = error.iter().map(ToString::to_string).collect::<Vec<_>>().join(": ");
+		match error_kind(error)
+		{
+			ErrorKind::UnknownSkill(name,suggestions) => object!
+			{
+				"error" => "unknown_skill",
+				"message" => message,
+				"name" => name.to_string(),
+				"suggestions" => suggestions.clone(),
+			},
+			ErrorKind::AmbiguousSkill(name,candidates) => object!
+			{
+				"error" => "ambiguous_skill",
+				"message" => message,
+				"name" => name.to_string(),
+				"candidates" => candidates.clone(),
+			},
+			ErrorKind::UnknownHero(name,names) => object!
+			{
+				"error" => "unknown_hero",
+				"message" => message,
+				"name" => name.to_string(),
+				"names" => names.clone(),
+			},
+			ErrorKind::AmbiguousHero(name,candidates) => object!
+			{
+				"error" => "ambiguous_hero",
+				"message" => message,
+				"name" => name.to_string(),
+				"candidates" => candidates.clone(),
+			},
+			ErrorKind::UnknownWeapon(name) => object!
+			{
+				"error" => "unknown_weapon",
+				"message" => message,
+				"name" => name.to_string(),
+			},
+			ErrorKind::CannotParry(talent) => object!
+			{
+				"error" => "cannot_parry",
+				"message" => message,
+				"talent" => talent.to_string(),
+			},
+			ErrorKind::UnknownQuality(name) => object!
+			{
+				"error" => "unknown_quality",
+				"message" => message,
+				"name" => name.to_string(),
+			},
+			ErrorKind::MissingQuality(name) => object!
+			{
+				"error" => "missing_quality",
+				"message" => message,
+				"name" => name.to_string(),
+			},
+			ErrorKind::MalformedProbe(skill,probe) => object!
+			{
+				"error" => "malformed_probe",
+				"message" => message,
+				"skill" => skill.to_string(),
+				"probe" => probe.to_string(),
+			},
+			ErrorKind::MissingElement(path) => object!
+			{
+				"error" => "missing_element",
+				"message" => message,
+				"path" => path.to_string(),
+			},
+			kind => object!
+			{
+				"error" => match kind
+				{
+					ErrorKind::Io(_) => "io",
+					ErrorKind::XmlParser(_) => "xml",
+					ErrorKind::NumberParsing(_) => "number",
+					ErrorKind::CommandLineParsing(_) => "command_line",
+					ErrorKind::LineEditing(_) => "line_editing",
+					ErrorKind::ShellSyntax(_) => "shell_syntax",
+					// e.g. a file which could not be read, with a message on top
+					_ if causes(error).any(|error| error.is::<std::io::Error>()) => "io",
+					_ => "other",
+				},
+				"message" => message,
+			},
+		}.dump()
+	}
 }
 
 /// Types of output generated by an *Action*.
@@ -162,6 +549,8 @@ pub enum Output
 	/// The result of a dice roll for a certain skill.
 	Roll
 	{
+		/// The hero who rolled, if rolled for several heroes at once.
+		hero: Option<String>,
 		/// Whether or not it was successful.
 		success: bool,
 		/// Whether the success/fail is critical.
@@ -172,6 +561,8 @@ pub enum Output
 		base: isize,
 		/// The sum of all modifiers placed on the roll.
 		mods: isize,
+		/// The effective encumbrance (BE) added to the modifiers.
+		encumbrance: isize,
 		/// Which qualities were rolled against.
 		checks: [Quality;3],
 		/// The corresponding stat values.
@@ -179,6 +570,110 @@ pub enum Output
 		/// The raw dice rolls.
 		dice: [isize;3],
 	},
+	/// Summary of a skill rolled for the whole party.
+	PartyRoll
+	{
+		/// The skill rolled for.
+		skill: String,
+		/// The heroes who succeeded with the most remaining points, all of them in case of a tie.
+		best: Vec<String>,
+		/// The remaining points of the best heroes, `None` if nobody succeeded.
+		remainder: Option<isize>,
+		/// The heroes lacking the skill.
+		missing: Vec<String>,
+		/// The heroes having the skill who could not roll for it, along with the reason, e.g. a missing quality.
+		failed: Vec<(String,String)>,
+	},
+	/// The exact probabilities of a roll for a certain skill.
+	Odds
+	{
+		/// The skill rolled for.
+		skill: String,
+		/// The skill value.
+		base: isize,
+		/// The sum of all modifiers placed on the roll.
+		mods: isize,
+		/// The effective encumbrance (BE) added to the modifiers.
+		encumbrance: isize,
+		/// Probability of a success, including critical successes.
+		success: f64,
+		/// Probability of a critical success.
+		critical_success: f64,
+		/// Probability of a critical failure.
+		critical_failure: f64,
+	},
+	/// The probabilities of a roll for a certain skill over a range of modifiers.
+	OddsTable
+	{
+		/// The skill rolled for.
+		skill: String,
+		/// The skill value.
+		base: isize,
+		/// The effective encumbrance (BE) added to every modifier.
+		encumbrance: isize,
+		/// The modifier, probability of a success, and the expected TaP* of each row.
+		rows: Vec<(isize,f64,f64)>,
+	},
+	/// The result of a single die roll for a quality.
+	Check
+	{
+		/// Whether or not it was successful.
+		success: bool,
+		/// Which quality was rolled against.
+		check: Quality,
+		/// The corresponding stat value.
+		value: isize,
+		/// The sum of all modifiers placed on the roll.
+		mods: isize,
+		/// The raw die roll.
+		die: isize,
+	},
+	/// The result of an attack or parry roll for a combat talent.
+	Combat
+	{
+		/// Whether or not it was successful.
+		success: bool,
+		/// Whether the success/fail is critical, i.e. confirmed.
+		critical: bool,
+		/// The combat talent used.
+		talent: String,
+		/// Whether it was an attack or a parry.
+		check: Quality,
+		/// The attack or parry value of the talent.
+		value: isize,
+		/// The sum of all modifiers placed on the roll.
+		mods: isize,
+		/// The raw die roll.
+		die: isize,
+		/// The confirmation roll on a 1 or 20.
+		confirmation: Option<isize>,
+	},
+	/// The hit points (TP) rolled for a weapon.
+	Damage
+	{
+		/// The name of the weapon.
+		weapon: String,
+		/// The hit points of the weapon.
+		damage: dice::Expression,
+		/// The raw dice rolls.
+		dice: Vec<isize>,
+		/// The bonus caused by the strength of the hero (TP/KK).
+		strength: isize,
+		/// The bonus passed by the user.
+		bonus: isize,
+		/// The total hit points dealt.
+		total: isize,
+	},
+	/// The result of a generic dice expression, e.g. 3W6+4.
+	Dice
+	{
+		/// The expression rolled.
+		expression: dice::Expression,
+		/// The raw dice rolls.
+		dice: Vec<isize>,
+		/// The sum of the dice and the modifier.
+		total: isize,
+	},
 	/// Any kind of gauge used to keep track during a cli session, e.g. health or stamina.
 	Gauge
 	{
@@ -189,7 +684,25 @@ pub enum Output
 		/// The maximum value.
 		max: isize,
 	},
+	/// A shortcut of the cli.
+	Alias
+	{
+		/// The name of the alias.
+		name: String,
+		/// The command line the alias expands to.
+		expansion: String,
+	},
+	/// The problems found while parsing a hero.
+	Validation
+	{
+		/// The name of the hero.
+		hero: String,
+		/// The parts of the xml which were skipped.
+		warnings: Vec<String>,
+	},
+	/// A single change of a gauge, as listed in the log of a session.
+	Log(session::Entry),
 	/// Dump of the hero structure.
-	Dump(Hero),
+	Dump(Box<Hero>),
 }
 
@@ -0,0 +1,414 @@
+//! Mutable state of a hero during a session.
+//!
+//! A *Session* holds the current and maximum values of all tracked gauges (health, astral points, stamina), as well as a log of their changes.
+//! It can be persisted to and restored from a JSON file so closing the terminal does not lose the current values.
+//!
+//! # Examples
+//!
+//! ```
+//! # use dsa::Hero;
+//! # use dsa::session::Session;
+//! let mut hero = Hero::default();
+//! hero.health = 30;
+//! let mut session = Session::new(&hero);
+//! session.gauges.get_mut("health").unwrap().current = 12;
+//!
+//! let restored = session.to_string().parse::<Session>().unwrap();
+//! assert_eq!(session, restored);
+//! assert_eq!(12, restored.gauges["health"].current);
+//! assert_eq!(30, restored.gauges["health"].max);
+//! ```
+
+use crate::error::*;
+use crate::hero::Hero;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Current and maximum value of a tracked attribute.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,Default)]
+pub struct Gauge
+{
+	/// The current value.
+	pub current: isize,
+	/// The maximum value.
+	pub max: isize,
+}
+
+impl Gauge
+{
+	/// A gauge filled up to the maximum.
+	pub fn full(max: isize) -> Self
+	{
+		Gauge
+		{
+			current: max,
+			max,
+		}
+	}
+}
+
+/// A single change of a gauge.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Entry
+{
+	/// When the change happened.
+	pub time: SystemTime,
+	/// The name of the changed gauge.
+	pub gauge: String,
+	/// The gauge before the change.
+	pub previous: Gauge,
+	/// The gauge after the change.
+	pub current: Gauge,
+	/// Why the gauge was changed, e.g. "orc axe".
+	pub reason: Option<String>,
+}
+
+/// All gauges of a hero, keyed by name.
+#[derive(Debug,Clone,PartialEq,Eq,Default)]
+pub struct Session
+{
+	/// The tracked gauges, e.g. health.
+	pub gauges: BTreeMap<String,Gauge>,
+	/// The history of all changes to the gauges, oldest first.
+	pub log: Vec<Entry>,
+	/// The maximum of health, astral points, and stamina according to the *Hero*, to tell a level-up from a maximum set by the user.
+	pub base: BTreeMap<String,isize>,
+}
+
+impl Session
+{
+	/// A fresh session with health, astral points, and stamina at their maximum.
+	pub fn new(hero: &Hero) -> Self
+	{
+		let base: BTreeMap<String,isize> = vec!
+			[ ("health".to_string(),hero.health)
+			, ("astral".to_string(),hero.astral)
+			, ("stamina".to_string(),hero.stamina)
+			].into_iter().collect();
+		Session
+		{
+			gauges: base.iter().map(|(name,max)| (name.clone(),Gauge::full(*max))).collect(),
+			log: vec![],
+			base,
+		}
+	}
+
+	/// Changes a gauge and records the change in the log, unless nothing changed.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::Hero;
+	/// # use dsa::session::{Gauge, Session};
+	/// # let mut hero = Hero::default();
+	/// hero.health = 30;
+	/// let mut session = Session::new(&hero);
+	/// session.update("health", Gauge { current: 24, max: 30 }, Some("orc axe".to_string()));
+	/// session.update("health", Gauge { current: 24, max: 30 }, None);
+	///
+	/// assert_eq!(1, session.log.len());
+	/// assert_eq!(30, session.log[0].previous.current);
+	/// assert_eq!(24, session.log[0].current.current);
+	/// assert_eq!(Some("orc axe"), session.log[0].reason.as_deref());
+	/// ```
+	pub fn update(&mut self, name: &str, gauge: Gauge, reason: Option<String>)
+	{
+		let previous = self.gauges.insert(name.to_string(), gauge).unwrap_or_default();
+		if previous != gauge
+		{
+			self.log.push(Entry
+			{
+				time: SystemTime::now(),
+				gauge: name.to_string(),
+				previous,
+				current: gauge,
+				reason,
+			});
+		}
+	}
+
+	/// Restores a session from a file, gauges missing in the file are taken from a fresh session.
+	///
+	/// The maximum of health, astral points, and stamina is taken from the *Hero* whenever it differs from the one the session was stored with, so a level-up is picked up right away.
+	/// Otherwise a maximum set by the user is kept.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use dsa::Hero;
+	/// # use dsa::session::Session;
+	/// # let mut hero = Hero::default();
+	/// # let path = std::env::temp_dir().join(format!("dsa-cli-doctest-{}.json", std::process::id()));
+	/// hero.health = 30;
+	/// hero.stamina = 30;
+	/// let mut session = Session::new(&hero);
+	/// session.gauges.get_mut("health").unwrap().current = 12;
+	/// session.save(&path).unwrap();
+	///
+	/// hero.health = 32;
+	/// hero.stamina = 28;
+	/// let session = Session::load(&hero, &path).unwrap();
+	/// # std::fs::remove_file(&path).unwrap();
+	/// assert_eq!(12, session.gauges["health"].current);
+	/// assert_eq!(32, session.gauges["health"].max);
+	/// assert_eq!(28, session.gauges["stamina"].current);
+	///
+	/// let mut session = session;
+	/// session.gauges.get_mut("stamina").unwrap().max = 35;
+	/// session.save(&path).unwrap();
+	/// let session = Session::load(&hero, &path).unwrap();
+	/// # std::fs::remove_file(&path).unwrap();
+	/// assert_eq!(35, session.gauges["stamina"].max);
+	/// ```
+	pub fn load<P: AsRef<Path>>(hero: &Hero, path: P) -> Result<Self>
+	{
+		let path = path.as_ref();
+		let mut session = Session::new(hero);
+		let stored = fs::read_to_string(path)
+			.chain_err(|| format!("loading session file '{}'", path.display()))?
+			.parse::<Session>()
+			.chain_err(|| format!("failed parsing session file '{}'", path.display()))?;
+		for (name,gauge) in stored.gauges
+		{
+			match session.gauges.get_mut(&name)
+			{
+				Some(fresh) if stored.base.get(&name) == Some(&fresh.max) => *fresh = gauge,
+				Some(fresh) => fresh.current = gauge.current.min(fresh.max),
+				None =>
+				{
+					session.gauges.insert(name,gauge);
+				},
+			}
+		}
+		session.log = stored.log;
+		Ok(session)
+	}
+
+	/// Persists the session to a file, creating missing parent directories.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()>
+	{
+		let path = path.as_ref();
+		if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty())
+		{
+			fs::create_dir_all(parent).chain_err(|| format!("writing session file '{}'", path.display()))?;
+		}
+		fs::write(path, format!("{}\n", self)).chain_err(|| format!("writing session file '{}'", path.display()))?;
+		Ok(())
+	}
+
+	/// The per-hero session file in the user's state directory, if it can be determined.
+	///
+	/// This is `$XDG_STATE_HOME/dsa-cli/<hero>.json`, falling back to `~/.local/state`.
+	pub fn default_path(hero: &Hero) -> Option<PathBuf>
+	{
+		let base = std::env::var_os("XDG_STATE_HOME")
+			.filter(|dir| !dir.is_empty())
+			.map(PathBuf::from)
+			.or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state")))?;
+		let name: String = hero.name.chars()
+			.map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+			.collect();
+		Some(base.join("dsa-cli").join(format!("{}.json", name)))
+	}
+}
+
+impl std::str::FromStr for Session
+{
+	type Err = Error;
+
+	fn from_str(input: &str) -> Result<Self>
+	{
+		let document = ::json::parse(input).chain_err(|| "session is not valid json")?;
+		let gauges = document["gauges"].entries()
+			.map(|(name,gauge)|
+			{
+				let value = |key: &str| gauge[key].as_isize().ok_or_else(|| format!("gauge '{}' lacks '{}'", name, key));
+				Ok((name.to_string(),Gauge
+				{
+					current: value("current")?,
+					max: value("max")?,
+				}))
+			})
+			.collect::<Result<_>>()?;
+		let log = document["log"].members()
+			.map(|entry|
+			{
+				let value = |key: &str, field: &str| entry[key][field].as_isize().ok_or_else(|| format!("log entry lacks '{}'", key));
+				Ok(Entry
+				{
+					time: UNIX_EPOCH + Duration::from_secs(entry["time"].as_u64().ok_or("log entry lacks 'time'")?),
+					gauge: entry["gauge"].as_str().ok_or("log entry lacks 'gauge'")?.to_string(),
+					previous: Gauge
+					{
+						current: value("previous","current")?,
+						max: value("previous","max")?,
+					},
+					current: Gauge
+					{
+						current: value("current","current")?,
+						max: value("current","max")?,
+					},
+					reason: entry["reason"].as_str().map(str::to_string),
+				})
+			})
+			.collect::<Result<_>>()?;
+		let base = document["base"].entries()
+			.map(|(name,max)| Ok((name.to_string(),max.as_isize().ok_or_else(|| format!("base of gauge '{}' is no integer", name))?)))
+			.collect::<Result<_>>()?;
+		Ok(Session
+		{
+			gauges,
+			log,
+			base,
+		})
+	}
+}
+
+impl std::fmt::Display for Session
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		use ::json::object;
+
+		let mut gauges = ::json::JsonValue::new_object();
+		for (name,gauge) in &self.gauges
+		{
+			gauges[name.as_str()] = object!
+			{
+				"current" => gauge.current,
+				"max" => gauge.max,
+			};
+		}
+		let log = self.log.iter()
+			.map(|entry| object!
+			{
+				"time" => entry.time.duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0),
+				"gauge" => entry.gauge.to_string(),
+				"previous" => object!
+				{
+					"current" => entry.previous.current,
+					"max" => entry.previous.max,
+				},
+				"current" => object!
+				{
+					"current" => entry.current.current,
+					"max" => entry.current.max,
+				},
+				"reason" => entry.reason.clone(),
+			})
+			.collect::<Vec<_>>();
+		let mut base = ::json::JsonValue::new_object();
+		for (name,max) in &self.base
+		{
+			base[name.as_str()] = (*max).into();
+		}
+		write!(f, "{}", object!{ "gauges" => gauges, "log" => log, "base" => base }.pretty(2))
+	}
+}
+
+/// Bounded undo and redo stacks of *Session* states.
+///
+/// Only the gauges are kept for every state, the log is merely truncated to its previous length on undo.
+///
+/// # Examples
+///
+/// ```
+/// # use dsa::Hero;
+/// # use dsa::session::{Gauge, Journal, Session};
+/// # let mut hero = Hero::default();
+/// hero.health = 30;
+/// let mut journal = Journal::new(10);
+/// let mut session = Session::new(&hero);
+///
+/// journal.record(&session);
+/// session.update("health", Gauge { current: 0, max: 30 }, None);
+///
+/// assert!(journal.undo(&mut session));
+/// assert_eq!(30, session.gauges["health"].current);
+/// assert!(session.log.is_empty());
+/// assert!(!journal.undo(&mut session));
+/// assert!(journal.redo(&mut session));
+/// assert_eq!(0, session.gauges["health"].current);
+/// assert_eq!(1, session.log.len());
+/// ```
+#[derive(Debug,Clone,Default)]
+pub struct Journal
+{
+	undo: VecDeque<Snapshot>,
+	redo: Vec<(BTreeMap<String,Gauge>,Vec<Entry>)>,
+	limit: usize,
+}
+
+/// The state of a *Session* before a change.
+#[derive(Debug,Clone)]
+struct Snapshot
+{
+	gauges: BTreeMap<String,Gauge>,
+	log: usize,
+}
+
+impl Journal
+{
+	/// An empty journal keeping at most *limit* states to undo.
+	pub fn new(limit: usize) -> Self
+	{
+		Journal
+		{
+			undo: VecDeque::new(),
+			redo: vec![],
+			limit,
+		}
+	}
+
+	/// Records the state before a change, discarding everything that could be redone.
+	pub fn record(&mut self, before: &Session)
+	{
+		self.redo.clear();
+		self.undo.push_back(Snapshot
+		{
+			gauges: before.gauges.clone(),
+			log: before.log.len(),
+		});
+		while self.undo.len() > self.limit
+		{
+			self.undo.pop_front();
+		}
+	}
+
+	/// Reverts the session to the last recorded state, returns false if there is nothing to undo.
+	pub fn undo(&mut self, session: &mut Session) -> bool
+	{
+		match self.undo.pop_back()
+		{
+			Some(previous) =>
+			{
+				let entries = session.log.split_off(previous.log.min(session.log.len()));
+				self.redo.push((std::mem::replace(&mut session.gauges, previous.gauges),entries));
+				true
+			},
+			None => false,
+		}
+	}
+
+	/// Reapplies the last undone state, returns false if there is nothing to redo.
+	pub fn redo(&mut self, session: &mut Session) -> bool
+	{
+		match self.redo.pop()
+		{
+			Some((gauges,entries)) =>
+			{
+				self.undo.push_back(Snapshot
+				{
+					gauges: std::mem::replace(&mut session.gauges, gauges),
+					log: session.log.len(),
+				});
+				session.log.extend(entries);
+				true
+			},
+			None => false,
+		}
+	}
+}